@@ -0,0 +1,249 @@
+use crate::checker::{git_command, git_path, run_command};
+use anyhow::Result;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+#[cfg(feature = "gix")]
+mod gitoxide;
+
+/// Ref sets used as `GitBackend::rev_list` tips (as in `git rev-list`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Revs {
+    /// HEAD (nothing for an unborn HEAD)
+    Head,
+    /// `refs/heads/`
+    Branches,
+    /// `refs/tags/`
+    Tags,
+    /// `refs/remotes/`
+    Remotes,
+}
+
+/// A ref and the objects it points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefTarget {
+    /// Full ref name (`refs/heads/main`)
+    pub name: String,
+    /// Object the ref points to (after following symbolic refs)
+    pub target: String,
+    /// Target with annotated tags peeled
+    pub peeled: String,
+}
+
+/// Repository access used by the built-in checks
+pub trait GitBackend: Send + Sync {
+    /// Absolute (per-worktree) git directory
+    fn git_dir(&self, repo_path: &Path) -> Result<PathBuf>;
+    /// Number of changed paths in the index and working tree, as listed by `git status --porcelain`
+    fn dirty_count(&self, repo_path: &Path, ignore_untracked: bool) -> Result<usize>;
+    /// Number of stash entries
+    fn stash_count(&self, repo_path: &Path) -> Result<usize>;
+    /// Configured remote names
+    fn remotes(&self, repo_path: &Path) -> Result<Vec<String>>;
+    /// HEAD commit (None for an unborn HEAD)
+    fn head(&self, repo_path: &Path) -> Result<Option<String>>;
+    /// Refs whose name starts with `prefix` ("" for all refs), sorted by name
+    fn refs(&self, repo_path: &Path, prefix: &str) -> Result<Vec<RefTarget>>;
+    /// Commits reachable from `include` but not from `exclude`
+    fn rev_list(&self, repo_path: &Path, include: &[Revs], exclude: &[Revs]) -> Result<Vec<String>>;
+}
+
+/// Which `GitBackend` the checks use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// Spawn `git` for every query
+    #[default]
+    Git,
+    /// Read the repository in-process with gitoxide
+    #[cfg(feature = "gix")]
+    Gix,
+}
+
+impl Backend {
+    /// Backends compiled into this build
+    pub const AVAILABLE: &[Backend] = &[
+        Backend::Git,
+        #[cfg(feature = "gix")]
+        Backend::Gix,
+    ];
+
+    pub fn get(self) -> &'static dyn GitBackend {
+        match self {
+            Backend::Git => &Subprocess,
+            #[cfg(feature = "gix")]
+            Backend::Gix => &gitoxide::Gitoxide,
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Git => write!(f, "git"),
+            #[cfg(feature = "gix")]
+            Backend::Gix => write!(f, "gix"),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "git" => Ok(Backend::Git),
+            #[cfg(feature = "gix")]
+            "gix" => Ok(Backend::Gix),
+            #[cfg(not(feature = "gix"))]
+            "gix" => Err("the gix backend is not available (build with --features gix)".to_string()),
+            _ => Err(format!("unknown backend: {} (expected git or gix)", value)),
+        }
+    }
+}
+
+fn lines(output: &str) -> impl Iterator<Item = &str> {
+    output.lines().filter(|l| !l.is_empty())
+}
+
+/// `git` subprocess backend
+struct Subprocess;
+
+impl GitBackend for Subprocess {
+    fn git_dir(&self, repo_path: &Path) -> Result<PathBuf> {
+        git_path(repo_path, &["rev-parse", "--absolute-git-dir"])
+    }
+
+    fn dirty_count(&self, repo_path: &Path, ignore_untracked: bool) -> Result<usize> {
+        let output = git_command(repo_path, &["status", "--porcelain"])?;
+        // Untracked files start with '??'
+        Ok(lines(&output)
+            .filter(|l| !(ignore_untracked && l.starts_with("??")))
+            .count())
+    }
+
+    fn stash_count(&self, repo_path: &Path) -> Result<usize> {
+        Ok(lines(&git_command(repo_path, &["stash", "list"])?).count())
+    }
+
+    fn remotes(&self, repo_path: &Path) -> Result<Vec<String>> {
+        Ok(lines(&git_command(repo_path, &["remote"])?).map(String::from).collect())
+    }
+
+    fn head(&self, repo_path: &Path) -> Result<Option<String>> {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(repo_path).args(["rev-parse", "--verify", "-q", "HEAD"]);
+        let output = run_command(command, "git rev-parse --verify -q HEAD")?;
+        match output.status.code() {
+            Some(0) => Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string())),
+            // Exits with 1 and no output for an unborn HEAD
+            Some(1) if output.stdout.is_empty() => Ok(None),
+            _ => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                anyhow::bail!("git rev-parse --verify -q HEAD failed: {}", stderr.trim())
+            }
+        }
+    }
+
+    fn refs(&self, repo_path: &Path, prefix: &str) -> Result<Vec<RefTarget>> {
+        // %(*objectname) is the peeled object for annotated tags, empty otherwise
+        let mut args = vec!["for-each-ref", "--format=%(refname) %(objectname) %(*objectname)"];
+        if !prefix.is_empty() {
+            args.push(prefix);
+        }
+        let output = git_command(repo_path, &args)?;
+        Ok(lines(&output)
+            .filter_map(|line| {
+                let mut fields = line.split(' ');
+                let name = fields.next()?;
+                let target = fields.next()?;
+                let peeled = fields.next().filter(|p| !p.is_empty()).unwrap_or(target);
+                Some(RefTarget {
+                    name: name.to_string(),
+                    target: target.to_string(),
+                    peeled: peeled.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn rev_list(&self, repo_path: &Path, include: &[Revs], exclude: &[Revs]) -> Result<Vec<String>> {
+        let arg = |revs: &Revs| match revs {
+            Revs::Head => "HEAD",
+            Revs::Branches => "--branches",
+            Revs::Tags => "--tags",
+            Revs::Remotes => "--remotes",
+        };
+        // --ignore-missing handles an unborn HEAD
+        let mut args = vec!["rev-list", "--ignore-missing"];
+        args.extend(include.iter().map(arg));
+        args.push("--not");
+        args.extend(exclude.iter().map(arg));
+        Ok(lines(&git_command(repo_path, &args)?).map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{git, setup_cloned_repo};
+    use std::fs;
+
+    #[test]
+    fn test_backends() {
+        let (base, _remote, repo) = setup_cloned_repo(&[]);
+        fs::write(repo.join("a.txt"), "a").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-m", "a.txt"]);
+        git(&repo, &["push", "origin", "HEAD"]);
+        git(&repo, &["commit", "--allow-empty", "-m", "local"]);
+        git(&repo, &["tag", "-a", "v1", "-m", "v1"]);
+        let head = git(&repo, &["rev-parse", "HEAD"]);
+        // Staged and then modified again: one porcelain line
+        fs::write(repo.join("a.txt"), "b").unwrap();
+        git(&repo, &["add", "."]);
+        fs::write(repo.join("a.txt"), "c").unwrap();
+        fs::write(repo.join("untracked.txt"), "").unwrap();
+
+        for backend in Backend::AVAILABLE {
+            let git = backend.get();
+            assert_eq!(git.git_dir(&repo).unwrap(), repo.join(".git").canonicalize().unwrap());
+            assert_eq!(git.dirty_count(&repo, false).unwrap(), 2, "{}", backend);
+            assert_eq!(git.dirty_count(&repo, true).unwrap(), 1, "{}", backend);
+            assert_eq!(git.stash_count(&repo).unwrap(), 0);
+            assert_eq!(git.remotes(&repo).unwrap(), vec!["origin"]);
+            assert_eq!(git.head(&repo).unwrap(), Some(head.clone()));
+
+            let tags = git.refs(&repo, "refs/tags/").unwrap();
+            assert_eq!(tags.len(), 1);
+            assert_eq!(tags[0].name, "refs/tags/v1");
+            assert_ne!(tags[0].target, head);
+            assert_eq!(tags[0].peeled, head);
+
+            assert_eq!(
+                git.rev_list(&repo, &[Revs::Branches], &[Revs::Remotes]).unwrap(),
+                vec![head.clone()],
+                "{}",
+                backend
+            );
+            assert!(git.rev_list(&repo, &[Revs::Tags], &[Revs::Branches]).unwrap().is_empty());
+        }
+
+        // An unborn HEAD is not an error, a missing repository is
+        git(base.path(), &["init", "-q", "empty"]);
+        for backend in Backend::AVAILABLE {
+            let git = backend.get();
+            assert_eq!(git.head(&base.path().join("empty")).unwrap(), None, "{}", backend);
+            assert!(git.head(base.path()).is_err(), "{}", backend);
+        }
+    }
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!("git".parse::<Backend>(), Ok(Backend::Git));
+        assert!("svn".parse::<Backend>().is_err());
+        #[cfg(not(feature = "gix"))]
+        assert!("gix".parse::<Backend>().is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use super::{GitBackend, RefTarget, Revs};
+use anyhow::Result;
+use gix::bstr::ByteSlice;
+use gix::object::Kind;
+use gix::status::UntrackedFiles;
+use gix::ObjectId;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// In-process backend using gitoxide
+pub(super) struct Gitoxide;
+
+fn open(repo_path: &Path) -> Result<gix::Repository> {
+    Ok(gix::open(repo_path)?)
+}
+
+/// Ref names and targets under `prefix`
+fn references(repo: &gix::Repository, prefix: &str) -> Result<Vec<RefTarget>> {
+    let platform = repo.references()?;
+    let iter = if prefix.is_empty() {
+        platform.all()?
+    } else {
+        platform.prefixed(prefix.as_bytes().as_bstr())?
+    };
+    let mut refs = Vec::new();
+    for reference in iter {
+        let mut reference = reference.map_err(|e| anyhow::anyhow!(e))?;
+        let name = reference.name().as_bstr().to_string();
+        let target = reference.follow_to_object()?.detach();
+        let peeled = reference.peel_to_id()?.detach();
+        refs.push(RefTarget {
+            name,
+            target: target.to_string(),
+            peeled: peeled.to_string(),
+        });
+    }
+    refs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(refs)
+}
+
+/// Commits pointed to by `revs` (refs to other objects are skipped)
+fn tips(repo: &gix::Repository, revs: &[Revs]) -> Result<BTreeSet<ObjectId>> {
+    let mut ids = BTreeSet::new();
+    for revs in revs {
+        let candidates: Vec<ObjectId> = match revs {
+            Revs::Head => repo.head()?.id().map(|id| id.detach()).into_iter().collect(),
+            Revs::Branches => peeled_ids(repo, "refs/heads/")?,
+            Revs::Tags => peeled_ids(repo, "refs/tags/")?,
+            Revs::Remotes => peeled_ids(repo, "refs/remotes/")?,
+        };
+        for id in candidates {
+            if repo.find_header(id)?.kind() == Kind::Commit {
+                ids.insert(id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+fn peeled_ids(repo: &gix::Repository, prefix: &str) -> Result<Vec<ObjectId>> {
+    references(repo, prefix)?
+        .into_iter()
+        .map(|r| Ok(ObjectId::from_hex(r.peeled.as_bytes())?))
+        .collect()
+}
+
+impl GitBackend for Gitoxide {
+    fn git_dir(&self, repo_path: &Path) -> Result<PathBuf> {
+        let repo = open(repo_path)?;
+        Ok(gix::path::realpath(repo.git_dir())?)
+    }
+
+    fn dirty_count(&self, repo_path: &Path, ignore_untracked: bool) -> Result<usize> {
+        let repo = open(repo_path)?;
+        let untracked = if ignore_untracked {
+            UntrackedFiles::None
+        } else {
+            UntrackedFiles::Collapsed
+        };
+        // Index and worktree changes to the same path are one porcelain line
+        let mut paths = BTreeSet::new();
+        for item in repo
+            .status(gix::progress::Discard)?
+            .untracked_files(untracked)
+            .into_iter(None)?
+        {
+            match item? {
+                gix::status::Item::IndexWorktree(item) => {
+                    // Ignored files and stat-only index updates are not changes
+                    if item.summary().is_none() {
+                        continue;
+                    }
+                    if let gix::status::index_worktree::Item::Rewrite { source, .. } = &item {
+                        paths.insert(source.rela_path().to_owned());
+                    }
+                    paths.insert(item.rela_path().to_owned());
+                }
+                gix::status::Item::TreeIndex(change) => {
+                    paths.insert(change.location().to_owned());
+                }
+            }
+        }
+        Ok(paths.len())
+    }
+
+    fn stash_count(&self, repo_path: &Path) -> Result<usize> {
+        let repo = open(repo_path)?;
+        let Some(stash) = repo.try_find_reference("refs/stash")? else {
+            return Ok(0);
+        };
+        let mut log = stash.log_iter();
+        match log.all()? {
+            Some(entries) => Ok(entries.filter(|e| e.is_ok()).count()),
+            None => Ok(0),
+        }
+    }
+
+    fn remotes(&self, repo_path: &Path) -> Result<Vec<String>> {
+        let repo = open(repo_path)?;
+        Ok(repo.remote_names().iter().map(|name| name.to_string()).collect())
+    }
+
+    fn head(&self, repo_path: &Path) -> Result<Option<String>> {
+        let repo = open(repo_path)?;
+        Ok(repo.head()?.id().map(|id| id.to_string()))
+    }
+
+    fn refs(&self, repo_path: &Path, prefix: &str) -> Result<Vec<RefTarget>> {
+        references(&open(repo_path)?, prefix)
+    }
+
+    fn rev_list(&self, repo_path: &Path, include: &[Revs], exclude: &[Revs]) -> Result<Vec<String>> {
+        let repo = open(repo_path)?;
+        let include = tips(&repo, include)?;
+        if include.is_empty() {
+            return Ok(Vec::new());
+        }
+        let exclude = tips(&repo, exclude)?;
+        let mut commits = Vec::new();
+        for info in repo.rev_walk(include).with_hidden(exclude).all()? {
+            commits.push(info?.id.to_string());
+        }
+        Ok(commits)
+    }
+}
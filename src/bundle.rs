@@ -0,0 +1,138 @@
+use crate::checker::git_command;
+use anyhow::{Context, Result};
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Namespace for temporary refs that export stash entries into the bundle
+const STASH_REF_PREFIX: &str = "refs/repo-check/stash";
+
+/// Pick a bundle file name in `bundle_dir` that does not exist yet
+fn bundle_path(repo_path: &Path, bundle_dir: &Path) -> PathBuf {
+    let name = repo_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+
+    let mut path = bundle_dir.join(format!("{}.bundle", name));
+    let mut suffix = 1;
+    while path.exists() {
+        path = bundle_dir.join(format!("{}-{}.bundle", name, suffix));
+        suffix += 1;
+    }
+    path
+}
+
+/// Export every stash entry as `refs/repo-check/stash/<n>` (only `refs/stash`
+/// itself is a ref; older entries live in its reflog). Created refs are pushed
+/// to `refs` so they can be removed even if a later entry fails.
+fn export_stash_refs(repo_path: &Path, refs: &mut Vec<String>) -> Result<()> {
+    let output = git_command(repo_path, &["stash", "list", "--format=%H"])?;
+    for (i, sha) in output.lines().filter(|l| !l.is_empty()).enumerate() {
+        let refname = format!("{}/{}", STASH_REF_PREFIX, i);
+        git_command(repo_path, &["update-ref", &refname, sha])?;
+        refs.push(refname);
+    }
+    Ok(())
+}
+
+/// Create `bundle_dir` and return it as an absolute path, with a relative
+/// path taken from `base` (git runs inside the repository, which would
+/// resolve it there)
+fn resolve_bundle_dir(bundle_dir: &Path, base: &Path) -> Result<PathBuf> {
+    let bundle_dir = base.join(bundle_dir);
+    fs::create_dir_all(&bundle_dir)
+        .context(format!("Failed to create bundle directory: {}", bundle_dir.display()))?;
+    bundle_dir
+        .canonicalize()
+        .context(format!("Failed to resolve bundle directory: {}", bundle_dir.display()))
+}
+
+/// Write `git bundle create --all` of the repository (including all stash
+/// entries) into `bundle_dir` (relative to the current directory) and verify
+/// it. Returns the (absolute) bundle path.
+pub fn create_bundle(repo_path: &Path, bundle_dir: &Path) -> Result<PathBuf> {
+    let bundle_dir = resolve_bundle_dir(bundle_dir, &env::current_dir()?)?;
+    let path = bundle_path(repo_path, &bundle_dir);
+    let path_arg = path.as_os_str();
+
+    let mut stash_refs = Vec::new();
+    let created = export_stash_refs(repo_path, &mut stash_refs)
+        .and_then(|_| git_command(repo_path, &[OsStr::new("bundle"), OsStr::new("create"), path_arg, OsStr::new("--all")]))
+        .and_then(|_| git_command(repo_path, &[OsStr::new("bundle"), OsStr::new("verify"), path_arg]));
+
+    // Remove the temporary refs again (the repository is kept if bundling failed)
+    for refname in &stash_refs {
+        let _ = git_command(repo_path, &["update-ref", "-d", refname]);
+    }
+
+    if let Err(e) = created {
+        let _ = fs::remove_file(&path);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::git;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_bundle_with_stashes() {
+        let repo = TempDir::new().unwrap();
+        let bundle_dir = TempDir::new().unwrap();
+        git(repo.path(), &["init"]);
+        fs::write(repo.path().join("test.txt"), "hello").unwrap();
+        git(repo.path(), &["add", "."]);
+        git(repo.path(), &["commit", "-m", "initial"]);
+        fs::write(repo.path().join("test.txt"), "stash 1").unwrap();
+        git(repo.path(), &["stash"]);
+        fs::write(repo.path().join("test.txt"), "stash 2").unwrap();
+        git(repo.path(), &["stash"]);
+
+        let path = create_bundle(repo.path(), &bundle_dir.path().join("bundles")).unwrap();
+        assert!(path.is_file());
+
+        // Both stash entries are in the bundle
+        let heads = git(repo.path(), &["bundle", "list-heads", path.to_str().unwrap()]);
+        assert!(heads.contains("refs/repo-check/stash/0"));
+        assert!(heads.contains("refs/repo-check/stash/1"));
+        // Temporary refs are removed from the repository
+        assert!(git(repo.path(), &["for-each-ref", STASH_REF_PREFIX]).is_empty());
+
+        // A second bundle of the same repository gets a new name
+        let second = create_bundle(repo.path(), &bundle_dir.path().join("bundles")).unwrap();
+        assert_ne!(path, second);
+    }
+
+    #[test]
+    fn test_create_bundle_relative_dir() {
+        let repo = TempDir::new().unwrap();
+        git(repo.path(), &["init"]);
+        git(repo.path(), &["commit", "--allow-empty", "-m", "initial"]);
+        fs::create_dir(repo.path().join("backups")).unwrap();
+
+        // Relative to the base (the current directory), not to the repository
+        let base = TempDir::new().unwrap();
+        let bundle_dir = resolve_bundle_dir(Path::new("backups"), base.path()).unwrap();
+        assert_eq!(bundle_dir, base.path().canonicalize().unwrap().join("backups"));
+
+        let path = create_bundle(repo.path(), &bundle_dir).unwrap();
+        assert!(path.starts_with(&bundle_dir));
+        assert!(path.is_file());
+        assert_eq!(fs::read_dir(repo.path().join("backups")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_create_bundle_empty_repo_fails() {
+        let repo = TempDir::new().unwrap();
+        let bundle_dir = TempDir::new().unwrap();
+        git(repo.path(), &["init"]);
+
+        assert!(create_bundle(repo.path(), bundle_dir.path()).is_err());
+        assert_eq!(fs::read_dir(bundle_dir.path()).unwrap().count(), 0);
+    }
+}
@@ -1,53 +1,217 @@
-use crate::types::{Reason, RepoResult};
+use crate::backend::{GitBackend, Revs};
+use crate::checks::Repo;
+use crate::paths;
+use crate::types::{BranchInfo, CheckOptions, Protection, Reason, RepoFingerprint, RepoResult, Status};
 use anyhow::Result;
-use std::path::Path;
-use std::process::Command;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wait_timeout::ChildExt;
 
-/// Execute a git command and return stdout
-fn git_command(repo_path: &Path, args: &[&str]) -> Result<String> {
-    let path_str = repo_path.to_str().ok_or_else(|| {
-        anyhow::anyhow!("Path is not valid UTF-8: {:?}", repo_path)
-    })?;
-    let output = Command::new("git")
-        .args(["-C", path_str])
-        .args(args)
-        .output()?;
+/// Per-command timeout used by the command line tool
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+thread_local! {
+    static COMMAND_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Applies a command timeout to the current thread until dropped
+pub(crate) struct TimeoutScope(Option<Duration>);
+
+impl TimeoutScope {
+    pub(crate) fn new(timeout: Option<Duration>) -> Self {
+        Self(COMMAND_TIMEOUT.replace(timeout))
+    }
+}
+
+impl Drop for TimeoutScope {
+    fn drop(&mut self) {
+        COMMAND_TIMEOUT.set(self.0);
+    }
+}
+
+/// A command was killed after running longer than the timeout
+#[derive(Debug)]
+pub struct TimedOut {
+    pub command: String,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} timed out after {:?}", self.command, self.timeout)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Run a command to completion, killing it when it exceeds the timeout of the
+/// running check (`CheckOptions::timeout`). `description` names it in errors.
+pub(crate) fn run_command(command: Command, description: &str) -> Result<Output> {
+    run_command_with_input(command, None, description)
+}
+
+/// `run_command`, writing `input` (if any) to the command's stdin
+fn run_command_with_input(mut command: Command, input: Option<Vec<u8>>, description: &str) -> Result<Output> {
+    let timeout = COMMAND_TIMEOUT.get();
+    if timeout.is_none() && input.is_none() {
+        return Ok(command.output()?);
+    }
+
+    // A group of its own, so a timeout also kills whatever the command started
+    // (e.g. git's remote helpers), which would otherwise keep the pipes open
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Drain both pipes while waiting so a chatty command cannot block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    // Write stdin from another thread for the same reason (dropping it closes the pipe)
+    let stdin = child.stdin.take();
+    let writer = input.map(|input| {
+        thread::spawn(move || {
+            if let Some(mut stdin) = stdin {
+                let _ = stdin.write_all(&input);
+            }
+        })
+    });
+
+    let status = match timeout {
+        Some(timeout) => child.wait_timeout(timeout)?,
+        None => Some(child.wait()?),
+    };
+    match status {
+        Some(status) => {
+            if let Some(writer) = writer {
+                let _ = writer.join();
+            }
+            Ok(Output {
+                status,
+                stdout: stdout.join().unwrap_or_default(),
+                stderr: stderr.join().unwrap_or_default(),
+            })
+        }
+        None => {
+            kill_group(&mut child);
+            let _ = child.wait();
+            // The pipe threads finish once the last process holding the pipes
+            // is gone; they are not waited for, in case one survived the kill
+            Err(TimedOut {
+                command: description.to_string(),
+                timeout: timeout.unwrap_or_default(),
+            }
+            .into())
+        }
+    }
+}
+
+/// Kill a command started by `run_command_with_input` and everything in its process group
+fn kill_group(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // SAFETY: kill(2) has no memory safety requirements; the group id is the
+        // child's pid, which is not reused before the child is waited for
+        let _ = unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+    }
+    let _ = child.kill();
+}
+
+/// Execute a git command and return the raw stdout
+/// (paths, in the repository path and in `args`, are passed as `OsStr`)
+pub(crate) fn git_command_bytes<S: AsRef<OsStr>>(repo_path: &Path, args: &[S]) -> Result<Vec<u8>> {
+    git_command_with_input(repo_path, args, None)
+}
+
+/// `git_command_bytes` with `input` on stdin (for `--stdin`, which avoids long argument lists)
+fn git_command_with_input<S: AsRef<OsStr>>(repo_path: &Path, args: &[S], input: Option<Vec<u8>>) -> Result<Vec<u8>> {
+    let description = args
+        .iter()
+        .map(|a| a.as_ref().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo_path).args(args);
+    let output = run_command_with_input(command, input, &format!("git {}", description))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git {} failed: {}", args.join(" "), stderr.trim());
+        anyhow::bail!("git {} failed: {}", description, stderr.trim());
+    }
+
+    Ok(output.stdout)
+}
+
+/// Execute a git command and return stdout
+pub(crate) fn git_command<S: AsRef<OsStr>>(repo_path: &Path, args: &[S]) -> Result<String> {
+    Ok(String::from_utf8_lossy(&git_command_bytes(repo_path, args)?).to_string())
+}
+
+/// Execute a git command that prints a single path (e.g. `rev-parse --git-dir`)
+pub(crate) fn git_path<S: AsRef<OsStr>>(repo_path: &Path, args: &[S]) -> Result<PathBuf> {
+    let output = git_command_bytes(repo_path, args)?;
+    Ok(paths::from_bytes(output.strip_suffix(b"\n").unwrap_or(&output)))
+}
+
+/// Current time in seconds since the Unix epoch
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Mark the result UNKNOWN after a failed command (`Reason::Timeout` if it was killed)
+pub fn record_error(result: &mut RepoResult, e: anyhow::Error) {
+    let reason = match e.downcast_ref::<TimedOut>() {
+        Some(timed_out) => Reason::Timeout(timed_out.command.clone()),
+        None => Reason::GitError(e.to_string()),
+    };
+    if !result.reasons.contains(&reason) {
+        result.mark_unknown(reason);
     }
+    result.errors.push(e.to_string());
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+fn timed_out(result: &RepoResult) -> bool {
+    result.reasons.iter().any(|r| matches!(r, Reason::Timeout(_)))
 }
 
 /// Check A: Uncommitted changes (working tree / index)
 pub fn check_uncommitted_changes(
+    git: &dyn GitBackend,
     repo_path: &Path,
     result: &mut RepoResult,
     ignore_untracked: bool,
 ) {
-    let output = match git_command(repo_path, &["status", "--porcelain"]) {
-        Ok(o) => o,
+    let dirty_count = match git.dirty_count(repo_path, ignore_untracked) {
+        Ok(n) => n,
         Err(e) => {
-            result.mark_unknown(Reason::GitError(e.to_string()));
-            result.errors.push(e.to_string());
+            record_error(result, e);
             return;
         }
     };
 
-    let mut dirty_count = 0;
-    for line in output.lines() {
-        if line.is_empty() {
-            continue;
-        }
-        // Untracked files start with '??'
-        if ignore_untracked && line.starts_with("??") {
-            continue;
-        }
-        dirty_count += 1;
-    }
-
     result.dirty_count = dirty_count;
     if dirty_count > 0 {
         result.mark_unsafe(Reason::UncommittedChanges);
@@ -55,81 +219,810 @@ pub fn check_uncommitted_changes(
 }
 
 /// Check B: Stash entries
-pub fn check_stash(repo_path: &Path, result: &mut RepoResult) {
-    let output = match git_command(repo_path, &["stash", "list"]) {
-        Ok(o) => o,
+pub fn check_stash(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult) {
+    let stash_count = match git.stash_count(repo_path) {
+        Ok(n) => n,
         Err(e) => {
-            result.mark_unknown(Reason::GitError(e.to_string()));
-            result.errors.push(e.to_string());
+            record_error(result, e);
             return;
         }
     };
 
-    let stash_count = output.lines().filter(|l| !l.is_empty()).count();
     result.stash_count = stash_count;
     if stash_count > 0 {
         result.mark_unsafe(Reason::StashExists);
     }
 }
 
+/// Number of fetches currently running (across all scan threads)
+static ACTIVE_FETCHES: Mutex<usize> = Mutex::new(0);
+static FETCH_FINISHED: Condvar = Condvar::new();
+
+/// One of the `CheckOptions::fetch_jobs` fetch slots, released when dropped
+struct FetchSlot;
+
+impl FetchSlot {
+    fn acquire(jobs: usize) -> Self {
+        let mut active = ACTIVE_FETCHES.lock().unwrap_or_else(|e| e.into_inner());
+        while jobs > 0 && *active >= jobs {
+            active = FETCH_FINISHED.wait(active).unwrap_or_else(|e| e.into_inner());
+        }
+        *active += 1;
+        FetchSlot
+    }
+}
+
+impl Drop for FetchSlot {
+    fn drop(&mut self) {
+        *ACTIVE_FETCHES.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+        FETCH_FINISHED.notify_one();
+    }
+}
+
+/// Refresh remote-tracking refs with `git fetch --all --prune` (at most `jobs`
+/// fetches at a time). A failed or timed-out fetch makes the result UNKNOWN,
+/// since the following checks would compare against stale refs.
+pub fn fetch_remotes(repo_path: &Path, result: &mut RepoResult, jobs: usize) {
+    let _slot = FetchSlot::acquire(jobs);
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_path)
+        .args(["fetch", "--all", "--prune", "--quiet"])
+        // Fail instead of prompting for credentials
+        .env("GIT_TERMINAL_PROMPT", "0");
+
+    let error = match run_command(command, "git fetch --all --prune") {
+        Ok(output) if output.status.success() => return,
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            if stderr.is_empty() {
+                format!("git fetch exited with {}", output.status)
+            } else {
+                stderr
+            }
+        }
+        Err(e) => e.to_string(),
+    };
+    // The first line names the problem; the rest is advice and per-remote summaries
+    let summary = error.lines().next().unwrap_or_default().to_string();
+    result.mark_unknown(Reason::FetchFailed(summary));
+    result.errors.push(error);
+}
+
+/// Newest reflog entry (seconds since the Unix epoch) of the reflogs under `dir`
+fn newest_reflog_entry(dir: &Path) -> Option<u64> {
+    let mut newest = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        let time = if path.is_dir() {
+            newest_reflog_entry(&path)
+        } else {
+            // "<old> <new> <committer> <timestamp> <tz>\t<message>"
+            fs::read_to_string(&path).ok().and_then(|log| {
+                let line = log.lines().rev().find(|l| !l.is_empty())?;
+                let identity = line.split('\t').next()?;
+                identity.rsplit(' ').nth(1)?.parse().ok()
+            })
+        };
+        newest = newest.max(time);
+    }
+    newest
+}
+
+/// When the remote tracking refs were last updated (seconds since the Unix
+/// epoch): the newer of FETCH_HEAD's mtime and the newest `refs/remotes/*` reflog entry.
+/// A failed fetch truncates FETCH_HEAD, so an empty one does not count.
+fn last_fetch_time(repo_path: &Path) -> Result<Option<u64>> {
+    let fetch_head = repo_path.join(git_path(repo_path, &["rev-parse", "--git-path", "FETCH_HEAD"])?);
+    let fetched = fs::metadata(fetch_head)
+        .ok()
+        .filter(|m| m.len() > 0)
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let common_dir = repo_path.join(git_path(repo_path, &["rev-parse", "--git-common-dir"])?);
+    let updated = newest_reflog_entry(&common_dir.join("logs/refs/remotes"));
+    Ok(fetched.max(updated))
+}
+
+/// Record how long ago the remote tracking refs were updated. With a maximum
+/// age, a repository whose refs are older (or whose age is unknown) is UNKNOWN,
+/// since Checks C–E may compare against a stale picture of the remotes.
+pub fn check_fetch_age(
+    git: &dyn GitBackend,
+    repo_path: &Path,
+    result: &mut RepoResult,
+    max_age: Option<Duration>,
+) {
+    let fetched = match last_fetch_time(repo_path) {
+        Ok(t) => t,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    let now = now();
+    result.last_fetch_age = fetched.map(|t| now.saturating_sub(t));
+
+    let Some(max_age) = max_age else {
+        return;
+    };
+    // Without remote tracking refs there is nothing to be stale (Check D)
+    match git.refs(repo_path, "refs/remotes/") {
+        Ok(refs) if refs.is_empty() => return,
+        Ok(_) => {}
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    }
+    if result.last_fetch_age.is_none_or(|age| age > max_age.as_secs()) {
+        result.mark_unknown(Reason::StaleRemoteRefs);
+    }
+}
+
 /// Check C: Local-only commits (across all branches)
-pub fn check_local_only_commits(repo_path: &Path, result: &mut RepoResult) {
+pub fn check_local_only_commits(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult) {
     // First, check if remote tracking refs exist
-    let remotes = match git_command(repo_path, &["remote"]) {
+    let remotes = match git.remotes(repo_path) {
         Ok(o) => o,
         Err(e) => {
-            result.mark_unknown(Reason::GitError(e.to_string()));
-            result.errors.push(e.to_string());
+            record_error(result, e);
             return;
         }
     };
 
     // Check if refs/remotes/* exists
-    let remote_refs = match git_command(repo_path, &["for-each-ref", "--format=%(refname)", "refs/remotes/"]) {
+    let remote_refs = match git.refs(repo_path, "refs/remotes/") {
         Ok(o) => o,
         Err(e) => {
-            result.mark_unknown(Reason::GitError(e.to_string()));
-            result.errors.push(e.to_string());
+            record_error(result, e);
             return;
         }
     };
 
-    if remotes.trim().is_empty() || remote_refs.trim().is_empty() {
+    if remotes.is_empty() || remote_refs.is_empty() {
         // No remote or no remote refs -> UNKNOWN
         result.mark_unknown(Reason::NoRemoteRefs);
         return;
     }
 
     // Detect commits that exist in local branches but not reachable from remotes
-    // git log --oneline --branches --not --remotes
-    let output = match git_command(repo_path, &["log", "--oneline", "--branches", "--not", "--remotes"]) {
-        Ok(o) => o,
+    // git rev-list --branches --not --remotes
+    let local_only = match git.rev_list(repo_path, &[Revs::Branches], &[Revs::Remotes]) {
+        Ok(commits) => commits,
         Err(e) => {
-            result.mark_unknown(Reason::GitError(e.to_string()));
-            result.errors.push(e.to_string());
+            record_error(result, e);
             return;
         }
     };
 
-    let local_only_count = output.lines().filter(|l| !l.is_empty()).count();
+    let local_only_count = local_only.len();
     result.local_only_commit_count = local_only_count;
     if local_only_count > 0 {
         result.mark_unsafe(Reason::LocalOnlyCommits);
     }
 }
 
+/// Objects the refs of `remote` point to right now (`git ls-remote`)
+fn ls_remote(repo_path: &Path, remote: &str, tags_only: bool) -> Result<Vec<(String, String)>> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo_path).arg("ls-remote");
+    if tags_only {
+        command.arg("--tags");
+    }
+    command.args(["--", remote]).env("GIT_TERMINAL_PROMPT", "0");
+    let description = format!("git ls-remote {}", remote);
+    let output = run_command(command, &description)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{} failed: {}", description, stderr.lines().next().unwrap_or_default());
+    }
+    // "<sha>\t<ref>" per line (peeled tags as "<ref>^{}")
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(sha, _)| !sha.is_empty())
+        .map(|(sha, name)| (sha.to_string(), name.to_string()))
+        .collect())
+}
+
+/// Path of a remote on the local filesystem (`file://` URLs and plain paths,
+/// relative ones against the working tree); None for network URLs, including
+/// scp-like `host:path`
+pub(crate) fn local_remote_path(url: &str, repo_path: &Path) -> Option<PathBuf> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+    if url.contains("://") {
+        return None;
+    }
+    // As in git: a colon before the first slash makes it scp-like
+    if let Some(colon) = url.find(':') {
+        if !url[..colon].contains('/') {
+            return None;
+        }
+    }
+    Some(repo_path.join(url))
+}
+
+/// Why a remote is dead (None if it looks alive). Local remotes must exist;
+/// others are only contacted (`git ls-remote`) with `probe`.
+fn dead_remote(repo_path: &Path, remote: &str, probe: bool) -> Result<Option<String>> {
+    let url = git_command(repo_path, &["remote", "get-url", remote])?;
+    let url = url.trim();
+    match local_remote_path(url, repo_path) {
+        Some(path) if !path.exists() => Ok(Some(format!("{}: {} does not exist", remote, path.display()))),
+        Some(_) => Ok(None),
+        None if probe => Ok(ls_remote(repo_path, remote, false).err().map(|e| format!("{}: {}", remote, e))),
+        None => Ok(None),
+    }
+}
+
+/// Remote health: a repository whose remotes are all dead (a local path that
+/// no longer exists, or with `probe` a URL `git ls-remote` fails for) is
+/// UNKNOWN, since its remote tracking refs can no longer be restored from.
+pub fn check_remote_health(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult, probe: bool) {
+    let remotes = match git.remotes(repo_path) {
+        Ok(remotes) => remotes,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    if remotes.is_empty() {
+        // Check D
+        return;
+    }
+
+    let mut problems = Vec::new();
+    for remote in &remotes {
+        match dead_remote(repo_path, remote, probe) {
+            Ok(Some(problem)) => problems.push(problem),
+            Ok(None) => return,
+            Err(e) => {
+                record_error(result, e);
+                return;
+            }
+        }
+    }
+    result.mark_unknown(Reason::RemoteUnreachable(remotes));
+    result.errors.extend(problems);
+}
+
+/// Check C, verified against the remotes: every local branch tip must be an
+/// ancestor of a ref that exists on a remote right now (`git ls-remote`), since
+/// remote tracking refs outlive branches deleted on the server. Remote refs
+/// whose objects were never fetched cannot be used (combine with `--fetch`).
+pub fn check_branches_on_remote(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult) {
+    let remotes = match git.remotes(repo_path) {
+        Ok(remotes) => remotes,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    if remotes.is_empty() {
+        // Nothing to verify against (Check D)
+        return;
+    }
+
+    // Without every remote's refs a branch could be missing only from our view
+    let mut remote_objects = Vec::new();
+    for remote in &remotes {
+        match ls_remote(repo_path, remote, false) {
+            Ok(refs) => remote_objects.extend(refs.into_iter().map(|(sha, _)| sha)),
+            Err(e) => {
+                record_error(result, e);
+                return;
+            }
+        }
+    }
+    remote_objects.sort();
+    remote_objects.dedup();
+
+    let branches = match git.refs(repo_path, "refs/heads/") {
+        Ok(refs) => refs,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    if branches.is_empty() {
+        return;
+    }
+
+    // One rev-list for all branches, with the remote objects excluded through
+    // stdin (there may be too many for the command line). A branch is missing
+    // from the remotes exactly when its own tip is listed. --ignore-missing
+    // skips remote objects that are not available locally.
+    let mut input = String::new();
+    for branch in &branches {
+        input.push_str(&branch.target);
+        input.push('\n');
+    }
+    for object in &remote_objects {
+        input.push('^');
+        input.push_str(object);
+        input.push('\n');
+    }
+    let output = match git_command_with_input(repo_path, &["rev-list", "--ignore-missing", "--stdin"], Some(input.into_bytes())) {
+        Ok(output) => String::from_utf8_lossy(&output).to_string(),
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    let unpushed: HashSet<&str> = output.lines().collect();
+    let missing: Vec<String> = branches
+        .iter()
+        .filter(|b| unpushed.contains(b.target.as_str()))
+        .map(|b| b.name.trim_start_matches("refs/heads/").to_string())
+        .collect();
+    if !missing.is_empty() {
+        result.mark_unsafe(Reason::BranchesNotOnRemote(missing));
+    }
+}
+
+/// Tags (ref name and object) on all remotes, or None if any of them cannot be
+/// queried (network remotes are only contacted with `probe`)
+fn remote_tags(repo_path: &Path, remotes: &[String], probe: bool) -> Option<HashSet<(String, String)>> {
+    let mut tags = HashSet::new();
+    for remote in remotes {
+        let url = git_command(repo_path, &["remote", "get-url", remote]).ok()?;
+        if !probe && local_remote_path(url.trim(), repo_path).is_none() {
+            return None;
+        }
+        for (object, name) in ls_remote(repo_path, remote, true).ok()? {
+            tags.insert((name, object));
+        }
+    }
+    Some(tags)
+}
+
+/// Check E: Local-only tags
+/// A tag is considered local-only when the commit it points to is not
+/// reachable from any remote tracking ref, or, when the remotes' tags can be
+/// listed (`remote_tags`), when no remote has a tag of that name and object.
+pub fn check_local_only_tags(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult, probe: bool) {
+    // Without remote tracking refs there is nothing to compare against (Check D)
+    let remote_refs = match git.refs(repo_path, "refs/remotes/") {
+        Ok(refs) => refs,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    if remote_refs.is_empty() {
+        return;
+    }
+
+    // Annotated tags are compared by the commit they point to
+    let tags = match git.refs(repo_path, "refs/tags/") {
+        Ok(refs) => refs,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    if tags.is_empty() {
+        return;
+    }
+
+    // Commits reachable from tags but not from any remote
+    let local_only_commits: HashSet<String> = match git.rev_list(repo_path, &[Revs::Tags], &[Revs::Remotes]) {
+        Ok(commits) => commits.into_iter().collect(),
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+
+    // A tag on a pushed commit can still be missing on the remotes
+    let remotes = match git.remotes(repo_path) {
+        Ok(remotes) => remotes,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    let remote_tags = remote_tags(repo_path, &remotes, probe);
+
+    let local_only_tag_count = tags
+        .iter()
+        .filter(|tag| {
+            local_only_commits.contains(&tag.peeled)
+                || remote_tags
+                    .as_ref()
+                    .is_some_and(|remote| !remote.contains(&(tag.name.clone(), tag.target.clone())))
+        })
+        .count();
+
+    result.local_only_tag_count = local_only_tag_count;
+    if local_only_tag_count > 0 {
+        result.mark_unsafe(Reason::LocalOnlyTags);
+    }
+}
+
+/// Check F: In-progress operations (rebase, merge, cherry-pick, revert, bisect)
+/// These keep work in the git directory (todo lists, ORIG_HEAD, ...) that a
+/// clean `git status --porcelain` does not reveal.
+pub fn check_in_progress_operations(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult) {
+    let git_dir = match git.git_dir(repo_path) {
+        Ok(dir) => dir,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+
+    let markers = [
+        ("rebase-merge", Reason::RebaseInProgress),
+        ("rebase-apply", Reason::RebaseInProgress),
+        ("MERGE_HEAD", Reason::MergeInProgress),
+        ("CHERRY_PICK_HEAD", Reason::CherryPickInProgress),
+        ("REVERT_HEAD", Reason::RevertInProgress),
+        ("BISECT_LOG", Reason::BisectInProgress),
+    ];
+
+    for (marker, reason) in markers {
+        if git_dir.join(marker).exists() && !result.reasons.contains(&reason) {
+            result.mark_unsafe(reason);
+        }
+    }
+}
+
+/// Check G: Commits only reachable from a detached HEAD
+pub fn check_detached_head_commits(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult) {
+    // Commits reachable from HEAD but not from any branch, tag or remote.
+    // Always empty unless HEAD is detached (or unborn).
+    let detached = match git.rev_list(repo_path, &[Revs::Head], &[Revs::Branches, Revs::Tags, Revs::Remotes]) {
+        Ok(commits) => commits,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+
+    let detached_count = detached.len();
+    result.detached_head_commit_count = detached_count;
+    if detached_count > 0 {
+        result.mark_unsafe(Reason::DetachedHeadCommits);
+    }
+}
+
+/// Check G (optional): Commits only recorded in reflogs (amended, reset or rebased away, ...)
+pub fn check_reflog_only_commits(repo_path: &Path, result: &mut RepoResult) {
+    // Older stash entries live in the refs/stash reflog; they (and the index and
+    // untracked commits they point to) are the stash check's business
+    let stashes = match git_command(repo_path, &["stash", "list", "--format=%H"]) {
+        Ok(o) => o,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    let mut input = String::new();
+    for stash in stashes.lines().filter(|l| !l.is_empty()) {
+        input.push('^');
+        input.push_str(stash);
+        input.push('\n');
+    }
+    let output = match git_command_with_input(repo_path, &["rev-list", "--reflog", "--stdin", "--not", "--all"], Some(input.into_bytes())) {
+        Ok(o) => String::from_utf8_lossy(&o).to_string(),
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+
+    let reflog_count = output.lines().filter(|l| !l.is_empty()).count();
+    result.reflog_only_commit_count = reflog_count;
+    if reflog_count > 0 {
+        result.mark_unsafe(Reason::ReflogOnlyCommits);
+    }
+}
+
+/// Extract linked worktree paths from `git worktree list --porcelain` output.
+/// The first entry is the main worktree; bare and prunable entries are skipped.
+fn parse_linked_worktrees(output: &[u8]) -> Vec<PathBuf> {
+    worktree_blocks(output)
+        .into_iter()
+        .skip(1)
+        .filter_map(|block| {
+            let mut path = None;
+            for line in block {
+                if let Some(p) = line.strip_prefix(b"worktree ") {
+                    path = Some(paths::from_bytes(p));
+                } else if line == b"bare" || line.starts_with(b"prunable") {
+                    return None;
+                }
+            }
+            path
+        })
+        .collect()
+}
+
+/// Lines of each blank-line separated `git worktree list --porcelain` entry
+fn worktree_blocks(output: &[u8]) -> Vec<Vec<&[u8]>> {
+    let lines: Vec<&[u8]> = output.split(|&b| b == b'\n').collect();
+    lines
+        .split(|line| line.is_empty())
+        .filter(|block| !block.is_empty())
+        .map(<[_]>::to_vec)
+        .collect()
+}
+
+/// Check H: Linked worktrees
+/// Every linked worktree is checked for per-worktree state (Checks A, F and G;
+/// stashes and refs are shared with the main worktree and checked there) and
+/// attached to the owning repository, since deleting it destroys the worktrees'
+/// uncommitted work.
+pub fn check_worktrees(repo_path: &Path, result: &mut RepoResult, options: &CheckOptions) {
+    let output = match git_command_bytes(repo_path, &["worktree", "list", "--porcelain"]) {
+        Ok(o) => o,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+
+    // Only the main worktree owns the others
+    let main_worktree = worktree_blocks(&output)
+        .first()
+        .and_then(|block| block.first())
+        .and_then(|line| line.strip_prefix(b"worktree "))
+        .map(paths::from_bytes);
+    let is_main = match (main_worktree.map(|p| p.canonicalize()), repo_path.canonicalize()) {
+        (Some(Ok(main)), Ok(repo)) => main == repo,
+        _ => false,
+    };
+    if !is_main {
+        return;
+    }
+
+    for worktree_path in parse_linked_worktrees(&output) {
+        let mut worktree = RepoResult::new(worktree_path.clone());
+        let git = options.backend.get();
+        if options.checks.is_enabled("uncommitted") {
+            check_uncommitted_changes(git, &worktree_path, &mut worktree, options.ignore_untracked);
+        }
+        if options.checks.is_enabled("in_progress") {
+            check_in_progress_operations(git, &worktree_path, &mut worktree);
+        }
+        if options.checks.is_enabled("detached_head") {
+            check_detached_head_commits(git, &worktree_path, &mut worktree);
+        }
+        worktree.finalize_safe();
+
+        propagate_child_status(result, worktree.status, Reason::WorktreeUnsafe, Reason::WorktreeUnknown);
+        result.worktrees.push(worktree);
+    }
+}
+
+/// Make the parent status at least as bad as a child's status
+fn propagate_child_status(
+    result: &mut RepoResult,
+    child_status: Status,
+    unsafe_reason: Reason,
+    unknown_reason: Reason,
+) {
+    match child_status {
+        Status::Unsafe if !result.reasons.contains(&unsafe_reason) => result.mark_unsafe(unsafe_reason),
+        Status::Unknown if !result.reasons.contains(&unknown_reason) => result.mark_unknown(unknown_reason),
+        _ => {}
+    }
+}
+
+/// Check I: Submodules
+/// Every initialized submodule is checked recursively with the full pipeline
+/// and attached to the superproject, whose status becomes the worst of them.
+pub fn check_submodules(repo_path: &Path, result: &mut RepoResult, options: &CheckOptions) {
+    if !repo_path.join(".gitmodules").is_file() {
+        return;
+    }
+
+    let output = match git_command_bytes(
+        repo_path,
+        &["config", "--file", ".gitmodules", "--null", "--get-regexp", r"^submodule\..*\.path$"],
+    ) {
+        Ok(o) => o,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+
+    // With --null each entry is "<key>\n<value>\0"
+    let mut submodule_paths: Vec<PathBuf> = output
+        .split(|&b| b == 0)
+        .filter_map(|entry| entry.iter().position(|&b| b == b'\n').map(|i| &entry[i + 1..]))
+        .map(|path| repo_path.join(paths::from_bytes(path)))
+        // Uninitialized submodules have no .git and hold no local work
+        .filter(|path| path.join(".git").exists())
+        .collect();
+    submodule_paths.sort();
+
+    for submodule_path in submodule_paths {
+        let submodule = check_repository(&submodule_path, options);
+        propagate_child_status(result, submodule.status, Reason::SubmoduleUnsafe, Reason::SubmoduleUnknown);
+        result.submodules.push(submodule);
+    }
+}
+
+/// Marker file (in the git directory) that protects a repository from deletion
+pub const PROTECT_MARKER_FILE: &str = "repo-check-keep";
+
+/// Git config key that protects a repository from deletion
+pub const PROTECT_CONFIG_KEY: &str = "repo-check.protect";
+
+/// Detect whether the repository is protected from deletion.
+/// Being protected does not affect the status, it only excludes the repository
+/// from deletion. If protection cannot be determined, the repository is UNKNOWN.
+pub fn check_protection(repo_path: &Path, result: &mut RepoResult, protected_paths: &[PathBuf]) {
+    if protected_paths.iter().any(|p| p == repo_path) {
+        result.protected = Some(Protection::ConfigList);
+        return;
+    }
+
+    // A plain `.git` directory needs no git call (which may hang like the checks did)
+    let dot_git = repo_path.join(".git");
+    let git_dir = if dot_git.is_dir() {
+        dot_git
+    } else {
+        match git_path(repo_path, &["rev-parse", "--git-common-dir"]) {
+            Ok(o) => repo_path.join(o),
+            Err(e) => {
+                record_error(result, e);
+                return;
+            }
+        }
+    };
+    if git_dir.join(PROTECT_MARKER_FILE).exists() {
+        result.protected = Some(Protection::MarkerFile);
+        return;
+    }
+
+    match git_command(repo_path, &["config", "--type=bool", "--default", "false", "--get", PROTECT_CONFIG_KEY]) {
+        Ok(o) if o.trim() == "true" => result.protected = Some(Protection::GitConfig),
+        Ok(_) => {}
+        Err(e) => {
+            record_error(result, e);
+        }
+    }
+}
+
+/// Record HEAD and all refs (including refs/stash) of the repository
+pub fn record_fingerprint(git: &dyn GitBackend, repo_path: &Path, result: &mut RepoResult) {
+    // None for an unborn HEAD
+    let head = match git.head(repo_path) {
+        Ok(head) => head,
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    let refs = match git.refs(repo_path, "") {
+        Ok(refs) => refs.into_iter().map(|r| (r.name, r.target)).collect(),
+        Err(e) => {
+            record_error(result, e);
+            return;
+        }
+    };
+    result.fingerprint = RepoFingerprint { head, refs };
+}
+
+/// Parse `%(upstream:track)` ("[ahead 1, behind 2]", "[gone]" or empty)
+/// into (ahead, behind, gone)
+fn parse_upstream_track(track: &str) -> (usize, usize, bool) {
+    let track = track.trim_start_matches('[').trim_end_matches(']');
+    if track == "gone" {
+        return (0, 0, true);
+    }
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            behind = n.parse().unwrap_or(0);
+        }
+    }
+    (ahead, behind, false)
+}
+
+/// Record every local branch with its upstream and tip commit. Local-only
+/// commits are counted per branch only when Check C found any. The breakdown
+/// is report-only: failures are listed in `errors` but leave the status alone.
+pub fn record_branches(repo_path: &Path, result: &mut RepoResult) {
+    let format = "--format=%(refname)%00%(upstream:short)%00%(upstream:track)%00%(objectname)%00%(committerdate:unix)%00%(contents:subject)";
+    let output = match git_command(repo_path, &["for-each-ref", format, "refs/heads/"]) {
+        Ok(o) => o,
+        Err(e) => {
+            result.errors.push(e.to_string());
+            return;
+        }
+    };
+
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('\0').collect();
+        let [refname, upstream, track, tip, date, subject] = fields[..] else {
+            continue;
+        };
+        let (ahead, behind, upstream_gone) = parse_upstream_track(track);
+        result.branches.push(BranchInfo {
+            name: refname.trim_start_matches("refs/heads/").to_string(),
+            upstream: Some(upstream.to_string()).filter(|u| !u.is_empty()),
+            upstream_gone,
+            ahead,
+            behind,
+            local_only_commit_count: 0,
+            tip: tip.to_string(),
+            subject: subject.to_string(),
+            date: date.parse().unwrap_or(0),
+        });
+    }
+
+    if result.local_only_commit_count == 0 {
+        return;
+    }
+    // All local-only commits with their parents in one command, then walked per branch
+    let output = match git_command(repo_path, &["rev-list", "--parents", "--branches", "--not", "--remotes"]) {
+        Ok(o) => o,
+        Err(e) => {
+            result.errors.push(e.to_string());
+            return;
+        }
+    };
+    let parents: HashMap<&str, Vec<&str>> = output
+        .lines()
+        .filter_map(|line| {
+            let mut shas = line.split(' ');
+            Some((shas.next()?, shas.collect()))
+        })
+        .collect();
+    for branch in &mut result.branches {
+        let mut seen = HashSet::new();
+        let mut pending = vec![branch.tip.as_str()];
+        while let Some(sha) = pending.pop() {
+            if let Some(commit_parents) = parents.get(sha) {
+                if seen.insert(sha) {
+                    pending.extend(commit_parents);
+                }
+            }
+        }
+        branch.local_only_commit_count = seen.len();
+    }
+}
+
 /// Run all checks on a repository
-pub fn check_repository(repo_path: &Path, ignore_untracked: bool) -> RepoResult {
+pub fn check_repository(repo_path: &Path, options: &CheckOptions) -> RepoResult {
     let mut result = RepoResult::new(repo_path.to_path_buf());
+    let repo = Repo {
+        path: repo_path,
+        options,
+    };
 
-    // Check A: Uncommitted changes
-    check_uncommitted_changes(repo_path, &mut result, ignore_untracked);
+    let _timeout = TimeoutScope::new(options.timeout);
+
+    // Checks A–I and custom checks, in registration order
+    for check in options.checks.enabled() {
+        check.run(&repo, &mut result);
+        // A hung repository would stall every remaining check as well
+        if timed_out(&result) {
+            break;
+        }
+    }
 
-    // Check B: Stash
-    check_stash(repo_path, &mut result);
+    // Protection, even after a timeout
+    check_protection(repo_path, &mut result, &options.protected_paths);
+    if timed_out(&result) {
+        return result;
+    }
 
-    // Check C: Local-only commits (includes Check D)
-    check_local_only_commits(repo_path, &mut result);
+    // HEAD and refs, to detect changes between scan and deletion
+    record_fingerprint(options.backend.get(), repo_path, &mut result);
 
     // Add reason if SAFE
     result.finalize_safe();
@@ -137,59 +1030,47 @@ pub fn check_repository(repo_path: &Path, ignore_untracked: bool) -> RepoResult
     result
 }
 
-/// Quick recheck before deletion (TOCTOU mitigation)
-/// Returns true if the repository still appears safe to delete.
-pub fn quick_recheck(repo_path: &Path) -> bool {
-    // Only check uncommitted changes as a fast safety check
-    match git_command(repo_path, &["status", "--porcelain"]) {
-        Ok(output) => output.trim().is_empty(),
-        Err(_) => false, // If git fails, assume not safe
-    }
+/// Full recheck before deletion (TOCTOU mitigation).
+/// Re-runs every check with the scan-time options and returns what differs
+/// from the scan-time result (empty if nothing changed).
+pub fn recheck(scan_result: &RepoResult, options: &CheckOptions) -> Vec<String> {
+    let fresh = check_repository(&scan_result.path, options);
+    scan_result.diff(&fresh)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::Backend;
     use std::fs;
+    use crate::test_util::{git, git_with_env, setup_cloned_repo, setup_git_repo};
     use std::process::Command;
     use tempfile::TempDir;
 
-    fn setup_git_repo() -> TempDir {
-        let dir = TempDir::new().unwrap();
-        Command::new("git")
-            .args(["init"])
-            .current_dir(dir.path())
-            .output()
-            .unwrap();
-        Command::new("git")
-            .args(["config", "user.email", "test@test.com"])
-            .current_dir(dir.path())
-            .output()
-            .unwrap();
-        Command::new("git")
-            .args(["config", "user.name", "Test"])
-            .current_dir(dir.path())
-            .output()
-            .unwrap();
-        dir
+    /// Check with every available backend; all of them must agree
+    fn check(repo_path: &Path, options: &CheckOptions) -> RepoResult {
+        let mut results = Backend::AVAILABLE.iter().map(|&backend| {
+            let options = CheckOptions {
+                backend,
+                ..options.clone()
+            };
+            (backend, check_repository(repo_path, &options))
+        });
+        let (_, first) = results.next().unwrap();
+        for (backend, result) in results {
+            assert_eq!(first.diff(&result), Vec::<String>::new(), "{} backend disagrees", backend);
+        }
+        first
     }
 
     #[test]
     fn test_clean_repo_no_remote() {
         let dir = setup_git_repo();
         fs::write(dir.path().join("test.txt"), "hello").unwrap();
-        Command::new("git")
-            .args(["add", "."])
-            .current_dir(dir.path())
-            .output()
-            .unwrap();
-        Command::new("git")
-            .args(["commit", "-m", "initial"])
-            .current_dir(dir.path())
-            .output()
-            .unwrap();
-
-        let result = check_repository(dir.path(), false);
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+
+        let result = check(dir.path(), &CheckOptions::default());
         // No remote -> UNKNOWN
         assert_eq!(result.status, crate::types::Status::Unknown);
     }
@@ -199,7 +1080,7 @@ mod tests {
         let dir = setup_git_repo();
         fs::write(dir.path().join("test.txt"), "hello").unwrap();
 
-        let result = check_repository(dir.path(), false);
+        let result = check(dir.path(), &CheckOptions::default());
         assert_eq!(result.status, crate::types::Status::Unsafe);
         assert!(result.dirty_count > 0);
     }
@@ -208,13 +1089,13 @@ mod tests {
     fn test_stash_detection() {
         let dir = setup_git_repo();
         std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
-        Command::new("git").args(["add", "."]).current_dir(dir.path()).output().unwrap();
-        Command::new("git").args(["commit", "-m", "initial"]).current_dir(dir.path()).output().unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
         // Create a stash
         std::fs::write(dir.path().join("test.txt"), "modified").unwrap();
-        Command::new("git").args(["stash"]).current_dir(dir.path()).output().unwrap();
+        git(dir.path(), &["stash"]);
 
-        let result = check_repository(dir.path(), false);
+        let result = check(dir.path(), &CheckOptions::default());
         assert_eq!(result.status, crate::types::Status::Unsafe);
         assert!(result.stash_count > 0);
     }
@@ -223,37 +1104,84 @@ mod tests {
     fn test_ignore_untracked() {
         let dir = setup_git_repo();
         std::fs::write(dir.path().join("committed.txt"), "hello").unwrap();
-        Command::new("git").args(["add", "."]).current_dir(dir.path()).output().unwrap();
-        Command::new("git").args(["commit", "-m", "initial"]).current_dir(dir.path()).output().unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
         // Add untracked file
         std::fs::write(dir.path().join("untracked.txt"), "new").unwrap();
 
         // Without ignore_untracked -> UNSAFE
-        let result = check_repository(dir.path(), false);
+        let result = check(dir.path(), &CheckOptions::default());
         assert_eq!(result.status, crate::types::Status::Unsafe);
 
         // With ignore_untracked -> still UNKNOWN because no remote
-        let result = check_repository(dir.path(), true);
+        let result = check(
+            dir.path(),
+            &CheckOptions {
+                ignore_untracked: true,
+                ..Default::default()
+            },
+        );
         // dirty_count should be 0 since untracked is ignored
         assert_eq!(result.dirty_count, 0);
     }
 
     #[test]
-    fn test_quick_recheck_clean() {
+    fn test_recheck_unchanged() {
         let dir = setup_git_repo();
         std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
-        Command::new("git").args(["add", "."]).current_dir(dir.path()).output().unwrap();
-        Command::new("git").args(["commit", "-m", "initial"]).current_dir(dir.path()).output().unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
 
-        assert!(quick_recheck(dir.path()));
+        let options = CheckOptions::default();
+        let scan_result = check(dir.path(), &options);
+        assert!(scan_result.fingerprint.head.is_some());
+        assert!(recheck(&scan_result, &options).is_empty());
     }
 
     #[test]
-    fn test_quick_recheck_dirty() {
+    fn test_recheck_detects_stash_and_commit() {
         let dir = setup_git_repo();
         std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+
+        let options = CheckOptions::default();
+        let scan_result = check(dir.path(), &options);
+
+        // A stash created after the scan leaves the working tree clean
+        std::fs::write(dir.path().join("test.txt"), "modified").unwrap();
+        git(dir.path(), &["stash"]);
+        let diff = recheck(&scan_result, &options);
+        assert!(diff.iter().any(|d| d.contains("Stash entries: 0 -> 1")));
+        assert!(diff.iter().any(|d| d.contains("refs/stash")));
 
-        assert!(!quick_recheck(dir.path()));
+        // A new commit moves HEAD
+        let scan_result = check(dir.path(), &options);
+        std::fs::write(dir.path().join("test2.txt"), "world").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "second"]);
+        let diff = recheck(&scan_result, &options);
+        assert!(diff.iter().any(|d| d.starts_with("HEAD:")));
+    }
+
+    #[test]
+    fn test_recheck_honours_ignore_untracked() {
+        let dir = setup_git_repo();
+        std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+        std::fs::write(dir.path().join("untracked.txt"), "new").unwrap();
+
+        let options = CheckOptions {
+            ignore_untracked: true,
+            ..Default::default()
+        };
+        let scan_result = check(dir.path(), &options);
+        assert!(recheck(&scan_result, &options).is_empty());
+
+        // Dirtying a tracked file is still detected
+        std::fs::write(dir.path().join("test.txt"), "modified").unwrap();
+        assert!(!recheck(&scan_result, &options).is_empty());
     }
 
     #[test]
@@ -261,26 +1189,608 @@ mod tests {
         let dir = setup_git_repo();
         // Create a bare repo as remote
         let remote_dir = tempfile::TempDir::new().unwrap();
-        Command::new("git").args(["init", "--bare"]).current_dir(remote_dir.path()).output().unwrap();
+        git(remote_dir.path(), &["init", "--bare"]);
 
         std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
-        Command::new("git").args(["add", "."]).current_dir(dir.path()).output().unwrap();
-        Command::new("git").args(["commit", "-m", "initial"]).current_dir(dir.path()).output().unwrap();
-        Command::new("git").args(["remote", "add", "origin", remote_dir.path().to_str().unwrap()]).current_dir(dir.path()).output().unwrap();
-        Command::new("git").args(["push", "-u", "origin", "HEAD"]).current_dir(dir.path()).output().unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+        git(dir.path(), &["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+        git(dir.path(), &["push", "-u", "origin", "HEAD"]);
 
         // All pushed -> SAFE
-        let result = check_repository(dir.path(), false);
+        let result = check(dir.path(), &CheckOptions::default());
         assert_eq!(result.status, crate::types::Status::Safe);
         assert_eq!(result.local_only_commit_count, 0);
 
         // Add unpushed commit -> UNSAFE
         std::fs::write(dir.path().join("test2.txt"), "world").unwrap();
-        Command::new("git").args(["add", "."]).current_dir(dir.path()).output().unwrap();
-        Command::new("git").args(["commit", "-m", "local only"]).current_dir(dir.path()).output().unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "local only"]);
 
-        let result = check_repository(dir.path(), false);
+        let result = check(dir.path(), &CheckOptions::default());
         assert_eq!(result.status, crate::types::Status::Unsafe);
         assert!(result.local_only_commit_count > 0);
     }
+
+    #[test]
+    fn test_fetch() {
+        let (_base, remote, repo) = setup_cloned_repo(&[]);
+        git(&repo, &["commit", "--allow-empty", "-m", "second"]);
+        git(&repo, &["push", "origin", "main"]);
+        // Remote-tracking ref behind the remote, as if pushed from elsewhere
+        git(&repo, &["update-ref", "refs/remotes/origin/main", "HEAD~1"]);
+        git(&repo, &["update-ref", "refs/remotes/origin/gone", "HEAD~1"]);
+
+        let mut options = CheckOptions {
+            fetch_jobs: 1,
+            ..Default::default()
+        };
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Unsafe);
+        assert_eq!(result.local_only_commit_count, 1);
+
+        options.checks.set_enabled("fetch", true).unwrap();
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Safe, "{:?}", result.reasons);
+        // Pruned
+        assert!(!result.fingerprint.refs.contains_key("refs/remotes/origin/gone"));
+
+        // Unreachable remote -> UNKNOWN, the other checks still run
+        fs::remove_dir_all(&remote).unwrap();
+        git(&repo, &["commit", "--allow-empty", "-m", "local"]);
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Unsafe);
+        assert!(result.reasons.iter().any(|r| matches!(r, Reason::FetchFailed(_))));
+        assert!(result.reasons.contains(&Reason::LocalOnlyCommits));
+    }
+
+    #[test]
+    fn test_fetch_age() {
+        // Reflog entries are timestamped with the committer date
+        let (base, remote, repo) = setup_cloned_repo(&[("GIT_COMMITTER_DATE", "@1577836800 +0000")]);
+
+        let mut options = CheckOptions::default();
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Safe);
+        let age = result.last_fetch_age.unwrap();
+        assert!(age > 365 * 86400, "{}", age);
+
+        options.max_fetch_age = Some(Duration::from_secs(14 * 86400));
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Unknown);
+        assert!(result.reasons.contains(&Reason::StaleRemoteRefs));
+
+        // A failed fetch empties FETCH_HEAD but does not refresh anything
+        let moved = base.path().join("moved.git");
+        fs::rename(&remote, &moved).unwrap();
+        assert!(!git_with_env(&repo, &["fetch", "origin"], &[]).status.success());
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Unknown);
+        assert!(result.last_fetch_age.unwrap() > 365 * 86400);
+        fs::rename(&moved, &remote).unwrap();
+
+        // A fetch (even one that brings nothing new) refreshes FETCH_HEAD
+        git(&repo, &["fetch", "origin"]);
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Safe);
+        assert!(result.last_fetch_age.unwrap() < 3600);
+
+        // Without remotes there is nothing to be stale
+        let dir = setup_git_repo();
+        let result = check(dir.path(), &options);
+        assert_eq!(result.last_fetch_age, None);
+        assert!(!result.reasons.contains(&Reason::StaleRemoteRefs));
+    }
+
+    #[test]
+    fn test_verify_remote() {
+        let (_base, remote, repo) = setup_cloned_repo(&[]);
+        git(&repo, &["checkout", "-b", "feature"]);
+        git(&repo, &["commit", "--allow-empty", "-m", "feature"]);
+        git(&repo, &["push", "origin", "feature"]);
+
+        let mut options = CheckOptions::default();
+        options.checks.set_enabled("verify_remote", true).unwrap();
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Safe, "{:?}", result.reasons);
+
+        // Deleted on the server, but the remote tracking ref is still there
+        git(&remote, &["branch", "-D", "feature"]);
+        assert_eq!(check(&repo, &CheckOptions::default()).status, Status::Safe);
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Unsafe);
+        assert!(result.reasons.contains(&Reason::BranchesNotOnRemote(vec!["feature".to_string()])));
+
+        // file:// URLs work the same way
+        let url = format!("file://{}", remote.display());
+        git(&repo, &["remote", "set-url", "origin", &url]);
+        git(&remote, &["branch", "feature", "main"]);
+        git(&repo, &["checkout", "-q", "--detach"]);
+        git(&repo, &["branch", "-f", "feature", "origin/main"]);
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Safe, "{:?}", result.reasons);
+
+        // An unreachable remote says nothing about which branches it has
+        let missing = remote.with_file_name("missing.git");
+        git(&repo, &["remote", "set-url", "origin", missing.to_str().unwrap()]);
+        let result = check(&repo, &options);
+        assert_eq!(result.status, Status::Unknown, "{:?}", result.reasons);
+        assert!(!result.reasons.iter().any(|r| matches!(r, Reason::BranchesNotOnRemote(_))));
+    }
+
+    #[test]
+    fn test_local_remote_path() {
+        let repo = Path::new("/src/repo");
+        assert_eq!(local_remote_path("/srv/a.git", repo), Some(PathBuf::from("/srv/a.git")));
+        assert_eq!(local_remote_path("../a.git", repo), Some(PathBuf::from("/src/repo/../a.git")));
+        assert_eq!(local_remote_path("file:///srv/a.git", repo), Some(PathBuf::from("/srv/a.git")));
+        assert_eq!(local_remote_path("./x:y", repo), Some(PathBuf::from("/src/repo/./x:y")));
+        assert_eq!(local_remote_path("https://example.com/a.git", repo), None);
+        assert_eq!(local_remote_path("ssh://git@example.com/a.git", repo), None);
+        assert_eq!(local_remote_path("git@example.com:org/a.git", repo), None);
+    }
+
+    #[test]
+    fn test_remote_health() {
+        let (base, remote, repo) = setup_cloned_repo(&[]);
+        let mirror = base.path().join("mirror.git");
+        git(base.path(), &["init", "--bare", "mirror.git"]);
+        git(&repo, &["remote", "add", "mirror", "../mirror.git"]);
+        assert_eq!(check(&repo, &CheckOptions::default()).status, Status::Safe);
+
+        // One remote left is enough
+        fs::remove_dir_all(&remote).unwrap();
+        assert_eq!(check(&repo, &CheckOptions::default()).status, Status::Safe);
+
+        fs::remove_dir_all(&mirror).unwrap();
+        let result = check(&repo, &CheckOptions::default());
+        assert_eq!(result.status, Status::Unknown);
+        assert!(result.reasons.contains(&Reason::RemoteUnreachable(vec!["mirror".into(), "origin".into()])));
+
+        // Network remotes are only contacted when probing
+        git(&repo, &["remote", "add", "web", "http://127.0.0.1:9/repo.git"]);
+        assert_eq!(check(&repo, &CheckOptions::default()).status, Status::Safe);
+        let options = CheckOptions {
+            probe_remotes: true,
+            ..Default::default()
+        };
+        let result = check(&repo, &options);
+        assert!(result.reasons.contains(&Reason::RemoteUnreachable(vec![
+            "mirror".into(),
+            "origin".into(),
+            "web".into()
+        ])));
+    }
+
+    #[test]
+    fn test_branches() {
+        assert_eq!(parse_upstream_track("[ahead 1, behind 2]"), (1, 2, false));
+        assert_eq!(parse_upstream_track("[behind 3]"), (0, 3, false));
+        assert_eq!(parse_upstream_track("[gone]"), (0, 0, true));
+        assert_eq!(parse_upstream_track(""), (0, 0, false));
+
+        let (_base, _remote, repo) = setup_cloned_repo(&[]);
+        git(&repo, &["commit", "--allow-empty", "-m", "second"]);
+        git(&repo, &["push", "origin", "main"]);
+        // main: ahead 1 and behind 1
+        git(&repo, &["reset", "--hard", "HEAD~1"]);
+        git(&repo, &["commit", "--allow-empty", "-m", "diverged"]);
+        // wip: no upstream, two commits of its own
+        git(&repo, &["checkout", "-b", "wip"]);
+        git(&repo, &["commit", "--allow-empty", "-m", "wip 1"]);
+        git(&repo, &["commit", "--allow-empty", "-m", "wip 2"]);
+
+        let result = check(&repo, &CheckOptions::default());
+        assert_eq!(result.status, Status::Unsafe);
+        let names: Vec<&str> = result.branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "wip"]);
+
+        let main = &result.branches[0];
+        assert_eq!(main.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((main.ahead, main.behind, main.upstream_gone), (1, 1, false));
+        assert_eq!(main.local_only_commit_count, 1);
+        assert_eq!(main.subject, "diverged");
+        assert!(main.date > 0);
+
+        let wip = &result.branches[1];
+        assert_eq!(wip.upstream, None);
+        assert_eq!(wip.local_only_commit_count, 3);
+        assert_eq!(wip.subject, "wip 2");
+        assert_eq!(wip.tip.len(), 40);
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["branches"][1]["name"], "wip");
+        assert_eq!(json["branches"][0]["behind"], 1);
+
+        // Report only: disabling it leaves the verdict unchanged
+        let mut options = CheckOptions::default();
+        options.checks.set_enabled("branches", false).unwrap();
+        let without = check(&repo, &options);
+        assert!(without.branches.is_empty());
+        assert_eq!((without.status, without.reasons), (result.status, result.reasons));
+    }
+
+    #[test]
+    fn test_local_only_tags() {
+        let dir = setup_git_repo();
+        let remote_dir = tempfile::TempDir::new().unwrap();
+        git(remote_dir.path(), &["init", "--bare"]);
+
+        std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+        git(dir.path(), &["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+        git(dir.path(), &["push", "-u", "origin", "HEAD"]);
+
+        // Tag on a pushed commit, but not pushed itself -> UNSAFE
+        git(dir.path(), &["tag", "-a", "v1", "-m", "v1"]);
+        let result = check(dir.path(), &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Unsafe);
+        assert_eq!(result.local_only_tag_count, 1);
+
+        // Pushed tag -> SAFE
+        git(dir.path(), &["push", "origin", "v1"]);
+        let result = check(dir.path(), &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Safe);
+        assert_eq!(result.local_only_tag_count, 0);
+
+        // Tag on a commit that only exists through the tag -> UNSAFE
+        std::fs::write(dir.path().join("test2.txt"), "world").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "release"]);
+        git(dir.path(), &["tag", "-a", "v2", "-m", "v2"]);
+        git(dir.path(), &["tag", "v2-light"]);
+        git(dir.path(), &["reset", "--hard", "HEAD~1"]);
+
+        let result = check(dir.path(), &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Unsafe);
+        assert_eq!(result.local_only_commit_count, 0);
+        assert_eq!(result.local_only_tag_count, 2);
+        assert!(result.reasons.contains(&Reason::LocalOnlyTags));
+    }
+
+    #[test]
+    fn test_in_progress_merge() {
+        let dir = setup_git_repo();
+        std::fs::write(dir.path().join("test.txt"), "base").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "base"]);
+        git(dir.path(), &["checkout", "-b", "other"]);
+        std::fs::write(dir.path().join("test.txt"), "other").unwrap();
+        git(dir.path(), &["commit", "-am", "other"]);
+        git(dir.path(), &["checkout", "-"]);
+        std::fs::write(dir.path().join("test.txt"), "main").unwrap();
+        git(dir.path(), &["commit", "-am", "main"]);
+        // Conflicting merge leaves MERGE_HEAD behind
+        git(dir.path(), &["merge", "other"]);
+
+        let result = check(dir.path(), &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Unsafe);
+        assert!(result.reasons.contains(&Reason::MergeInProgress));
+    }
+
+    #[test]
+    fn test_in_progress_markers() {
+        let dir = setup_git_repo();
+        let git_dir = dir.path().join(".git");
+        fs::create_dir(git_dir.join("rebase-merge")).unwrap();
+        fs::create_dir(git_dir.join("rebase-apply")).unwrap();
+        fs::write(git_dir.join("CHERRY_PICK_HEAD"), "").unwrap();
+        fs::write(git_dir.join("REVERT_HEAD"), "").unwrap();
+        fs::write(git_dir.join("BISECT_LOG"), "").unwrap();
+
+        for backend in Backend::AVAILABLE {
+            let mut result = RepoResult::new(dir.path().to_path_buf());
+            check_in_progress_operations(backend.get(), dir.path(), &mut result);
+            assert_eq!(result.status, crate::types::Status::Unsafe);
+            assert_eq!(
+                result.reasons,
+                vec![
+                    Reason::RebaseInProgress,
+                    Reason::CherryPickInProgress,
+                    Reason::RevertInProgress,
+                    Reason::BisectInProgress,
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_detached_head_commits() {
+        let dir = setup_git_repo();
+        let remote_dir = tempfile::TempDir::new().unwrap();
+        git(remote_dir.path(), &["init", "--bare"]);
+
+        std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+        git(dir.path(), &["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+        git(dir.path(), &["push", "-u", "origin", "HEAD"]);
+
+        // Detached at a pushed commit -> SAFE
+        git(dir.path(), &["checkout", "--detach"]);
+        let result = check(dir.path(), &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Safe);
+        assert_eq!(result.detached_head_commit_count, 0);
+
+        // Commit on detached HEAD -> UNSAFE
+        std::fs::write(dir.path().join("test2.txt"), "experiment").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "experiment"]);
+
+        let result = check(dir.path(), &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Unsafe);
+        assert_eq!(result.detached_head_commit_count, 1);
+        assert!(result.reasons.contains(&Reason::DetachedHeadCommits));
+    }
+
+    #[test]
+    fn test_reflog_only_commits() {
+        let dir = setup_git_repo();
+        let remote_dir = tempfile::TempDir::new().unwrap();
+        git(remote_dir.path(), &["init", "--bare"]);
+
+        std::fs::write(dir.path().join("test.txt"), "hello").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+        git(dir.path(), &["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+        git(dir.path(), &["push", "-u", "origin", "HEAD"]);
+
+        // Commit and then reset it away: only the reflog remembers it
+        std::fs::write(dir.path().join("test2.txt"), "discarded").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-m", "discarded"]);
+        git(dir.path(), &["reset", "--hard", "HEAD~1"]);
+
+        // Reflog check is opt-in
+        let result = check(dir.path(), &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Safe);
+
+        let mut options = CheckOptions::default();
+        options.checks.set_enabled("reflog", true).unwrap();
+        let result = check(dir.path(), &options);
+        assert_eq!(result.status, crate::types::Status::Unsafe);
+        assert_eq!(result.reflog_only_commit_count, 1);
+        assert!(result.reasons.contains(&Reason::ReflogOnlyCommits));
+
+        // Older stash entries are only in the refs/stash reflog but left to the stash check
+        git(dir.path(), &["reflog", "expire", "--expire=now", "--all"]);
+        for content in ["one", "two"] {
+            std::fs::write(dir.path().join("test.txt"), content).unwrap();
+            git(dir.path(), &["stash"]);
+        }
+        let result = check(dir.path(), &options);
+        assert_eq!(result.stash_count, 2);
+        assert_eq!(result.reflog_only_commit_count, 0);
+        assert!(!result.reasons.contains(&Reason::ReflogOnlyCommits));
+    }
+
+    #[test]
+    fn test_parse_linked_worktrees() {
+        let output = "worktree /repo\nHEAD abc\nbranch refs/heads/main\n\n\
+                      worktree /wt\nHEAD abc\nbranch refs/heads/feature\n\n\
+                      worktree /gone\nHEAD abc\ndetached\nprunable gitdir file points to non-existent location\n\n";
+        assert_eq!(parse_linked_worktrees(output.as_bytes()), vec![PathBuf::from("/wt")]);
+    }
+
+    #[test]
+    fn test_dirty_linked_worktree() {
+        let base = tempfile::TempDir::new().unwrap();
+        let main = base.path().join("main");
+        let worktree = base.path().join("wt");
+        fs::create_dir(&main).unwrap();
+        git(&main, &["init"]);
+        git(&main, &["config", "user.email", "test@test.com"]);
+        git(&main, &["config", "user.name", "Test"]);
+        git(&main, &["commit", "--allow-empty", "-m", "initial"]);
+        git(&main, &["worktree", "add", "-b", "feature", worktree.to_str().unwrap()]);
+
+        // Clean worktree is attached but does not affect the main repo's checks
+        let result = check(&main, &CheckOptions::default());
+        assert_eq!(result.worktrees.len(), 1);
+        assert_eq!(result.worktrees[0].status, crate::types::Status::Safe);
+        assert!(!result.reasons.contains(&Reason::WorktreeUnsafe));
+
+        // Dirty worktree makes the main repo UNSAFE
+        fs::write(worktree.join("wip.txt"), "wip").unwrap();
+        let result = check(&main, &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Unsafe);
+        assert!(result.reasons.contains(&Reason::WorktreeUnsafe));
+        assert_eq!(result.worktrees[0].dirty_count, 1);
+
+        // Checking the linked worktree itself does not report its siblings
+        let result = check(&worktree, &CheckOptions::default());
+        assert!(result.worktrees.is_empty());
+        assert_eq!(result.dirty_count, 1);
+
+        // The stash is shared by all worktrees and only counted for the main one
+        fs::remove_file(worktree.join("wip.txt")).unwrap();
+        fs::write(main.join("stashed.txt"), "wip").unwrap();
+        git(&main, &["add", "."]);
+        git(&main, &["stash"]);
+        let result = check(&main, &CheckOptions::default());
+        assert_eq!(result.stash_count, 1);
+        assert_eq!(result.worktrees[0].stash_count, 0);
+        assert_eq!(result.worktrees[0].status, crate::types::Status::Safe);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths() {
+        let base = tempfile::TempDir::new().unwrap();
+        let repo = base.path().join(paths::from_bytes(b"caf\xe9"));
+        let worktree = base.path().join(paths::from_bytes(b"wt-\xff"));
+        let remote = base.path().join("remote.git");
+        let git = |args: &[&OsStr]| {
+            let output = Command::new("git")
+                .args(["-c", "user.email=test@test.com", "-c", "user.name=Test"])
+                .args(args)
+                .current_dir(base.path())
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+        };
+        git(&[OsStr::new("init"), OsStr::new("--bare"), remote.as_os_str()]);
+        git(&[OsStr::new("clone"), remote.as_os_str(), repo.as_os_str()]);
+        git(&[OsStr::new("-C"), repo.as_os_str(), OsStr::new("commit"), OsStr::new("--allow-empty"), OsStr::new("-m"), OsStr::new("initial")]);
+        git(&[OsStr::new("-C"), repo.as_os_str(), OsStr::new("push"), OsStr::new("origin"), OsStr::new("HEAD")]);
+        git(&[OsStr::new("-C"), repo.as_os_str(), OsStr::new("worktree"), OsStr::new("add"), OsStr::new("--detach"), worktree.as_os_str()]);
+
+        let result = check(&repo, &CheckOptions::default());
+        assert_eq!(result.status, Status::Safe, "{:?}", result.reasons);
+        assert_eq!(result.worktrees.len(), 1);
+        assert_eq!(result.worktrees[0].path, worktree.canonicalize().unwrap());
+
+        // The git directory is found, so markers inside it are honoured
+        fs::write(repo.join(".git").join(PROTECT_MARKER_FILE), "").unwrap();
+        fs::write(repo.join(".git").join("MERGE_HEAD"), "").unwrap();
+        let result = check(&repo, &CheckOptions::default());
+        assert_eq!(result.protected, Some(Protection::MarkerFile));
+        assert!(result.reasons.contains(&Reason::MergeInProgress));
+
+        // JSON keeps the exact path
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains("\"path_bytes\""));
+        let parsed: RepoResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.path, repo);
+        assert_eq!(parsed.worktrees[0].path, result.worktrees[0].path);
+    }
+
+    #[test]
+    fn test_submodule_local_only_commits() {
+        let base = tempfile::TempDir::new().unwrap();
+        // Submodules are cloned from local paths
+        let file_protocol = [
+            ("GIT_CONFIG_COUNT", "1"),
+            ("GIT_CONFIG_KEY_0", "protocol.file.allow"),
+            ("GIT_CONFIG_VALUE_0", "always"),
+        ];
+        let git = |dir: &Path, args: &[&str]| git_with_env(dir, args, &file_protocol);
+
+        // Remotes for the submodule and the superproject
+        let sub_remote = base.path().join("sub.git");
+        let super_remote = base.path().join("super.git");
+        let seed = base.path().join("seed");
+        fs::create_dir(&seed).unwrap();
+        git(base.path(), &["init", "--bare", sub_remote.to_str().unwrap()]);
+        git(base.path(), &["init", "--bare", super_remote.to_str().unwrap()]);
+        git(&seed, &["init"]);
+        git(&seed, &["commit", "--allow-empty", "-m", "sub initial"]);
+        git(&seed, &["push", sub_remote.to_str().unwrap(), "HEAD"]);
+
+        let superproject = base.path().join("super");
+        fs::create_dir(&superproject).unwrap();
+        git(&superproject, &["init"]);
+        git(&superproject, &["remote", "add", "origin", super_remote.to_str().unwrap()]);
+        git(&superproject, &["submodule", "add", sub_remote.to_str().unwrap(), "sub"]);
+        git(&superproject, &["commit", "-m", "add submodule"]);
+        git(&superproject, &["push", "-u", "origin", "HEAD"]);
+
+        let result = check(&superproject, &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Safe);
+        assert_eq!(result.submodules.len(), 1);
+        assert_eq!(result.submodules[0].status, crate::types::Status::Safe);
+
+        // Unpushed commit inside the submodule -> superproject UNSAFE
+        let sub = superproject.join("sub");
+        git(&sub, &["checkout", "-b", "work"]);
+        git(&sub, &["commit", "--allow-empty", "-m", "sub local"]);
+
+        let result = check(&superproject, &CheckOptions::default());
+        assert_eq!(result.status, crate::types::Status::Unsafe);
+        assert!(result.reasons.contains(&Reason::SubmoduleUnsafe));
+        assert_eq!(result.submodules[0].local_only_commit_count, 1);
+    }
+
+    #[test]
+    fn test_protection() {
+        let dir = setup_git_repo();
+        let options = CheckOptions::default();
+        assert_eq!(check(dir.path(), &options).protected, None);
+
+        // Git config key
+        git(dir.path(), &["config", PROTECT_CONFIG_KEY, "true"]);
+        assert_eq!(check(dir.path(), &options).protected, Some(Protection::GitConfig));
+
+        // Marker file takes precedence over git config
+        fs::write(dir.path().join(".git").join(PROTECT_MARKER_FILE), "").unwrap();
+        assert_eq!(check(dir.path(), &options).protected, Some(Protection::MarkerFile));
+
+        // Config list takes precedence over both
+        let options = CheckOptions {
+            protected_paths: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let result = check(dir.path(), &options);
+        assert_eq!(result.protected, Some(Protection::ConfigList));
+        // Status is unaffected
+        assert_eq!(result.status, crate::types::Status::Unknown);
+    }
+
+    #[test]
+    fn test_protection_after_timeout() {
+        let dir = setup_git_repo();
+        fs::write(dir.path().join(".git").join(PROTECT_MARKER_FILE), "").unwrap();
+        let mut options = CheckOptions {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        options.checks.register(crate::checks::CommandCheck {
+            name: "slow".to_string(),
+            command: "sleep 5".to_string(),
+            description: None,
+        });
+
+        let result = check_repository(dir.path(), &options);
+        assert!(result.reasons.contains(&Reason::Timeout("sleep 5".to_string())));
+        assert_eq!(result.protected, Some(Protection::MarkerFile));
+    }
+
+    #[test]
+    fn test_command_timeout() {
+        let _timeout = TimeoutScope::new(Some(Duration::from_millis(200)));
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let start = std::time::Instant::now();
+        let err = run_command(command, "sleep 5").unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(3));
+        assert_eq!(err.to_string(), "sleep 5 timed out after 200ms");
+
+        let mut result = RepoResult::new(PathBuf::from("/repo"));
+        record_error(&mut result, err);
+        assert_eq!(result.status, Status::Unknown);
+        assert_eq!(result.reasons, vec![Reason::Timeout("sleep 5".to_string())]);
+
+        // Output of commands finishing in time is still collected
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo out; echo err >&2"]);
+        let output = run_command(command, "echo").unwrap();
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_command_timeout_kills_children() {
+        let dir = TempDir::new().unwrap();
+        let pid_file = dir.path().join("pid");
+        let _timeout = TimeoutScope::new(Some(Duration::from_millis(200)));
+        // The background sleep keeps stdin and stdout open and never reads its input
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(format!("sleep 5 & echo $! > {}; wait", pid_file.display()));
+        let start = std::time::Instant::now();
+        let err = run_command_with_input(command, Some(vec![b'x'; 1 << 20]), "sh").unwrap_err();
+        assert!(err.is::<TimedOut>());
+        assert!(start.elapsed() < Duration::from_secs(3));
+
+        // Gone (or a zombie waiting to be reaped) shortly after the kill
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let stat = PathBuf::from("/proc").join(pid.trim()).join("stat");
+        let alive = || fs::read_to_string(&stat).is_ok_and(|s| !s.contains(") Z "));
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        while alive() && std::time::Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!alive());
+    }
 }
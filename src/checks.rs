@@ -0,0 +1,600 @@
+use crate::backend::GitBackend;
+use crate::checker::{self, git_command, TimedOut};
+use crate::types::{CheckOptions, Reason, RepoResult};
+use anyhow::Result;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+/// A repository being checked
+pub struct Repo<'a> {
+    /// Working tree path
+    pub path: &'a Path,
+    /// Options of the current scan
+    pub options: &'a CheckOptions,
+}
+
+impl Repo<'_> {
+    /// Execute a git command in the repository and return stdout
+    pub fn git(&self, args: &[&str]) -> Result<String> {
+        git_command(self.path, args)
+    }
+
+    /// Backend selected in the options
+    pub fn backend(&self) -> &'static dyn GitBackend {
+        self.options.backend.get()
+    }
+}
+
+/// A safety check run on every repository.
+/// Checks record what they find with `RepoResult::mark_unsafe` / `mark_unknown`;
+/// a repository nothing was found in becomes SAFE.
+pub trait Check: Send + Sync {
+    /// Name used to enable or disable the check (`--enable` / `--disable`, `[checks]`)
+    fn name(&self) -> &str;
+    /// One-line description for `repo-check checks`
+    fn description(&self) -> &str;
+    /// Whether the check runs unless explicitly enabled
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult);
+}
+
+/// Built-in checks that must run before anything is deleted: without them a
+/// repository with local work (or without a remote) could come out SAFE
+pub const CORE_CHECKS: &[&str] = &[
+    "uncommitted",
+    "stash",
+    "local_commits",
+    "tags",
+    "in_progress",
+    "detached_head",
+    "worktrees",
+    "submodules",
+];
+
+#[derive(Clone)]
+struct Entry {
+    check: Arc<dyn Check>,
+    enabled: bool,
+}
+
+/// Registered checks (in run order) and whether each is enabled
+#[derive(Clone)]
+pub struct Checks {
+    entries: Vec<Entry>,
+}
+
+impl Checks {
+    /// No checks at all
+    pub fn empty() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// The built-in checks A–I
+    pub fn builtin() -> Self {
+        let mut checks = Self::empty();
+        checks
+            .register(Uncommitted)
+            .register(Stash)
+            .register(Fetch)
+            .register(FetchAge)
+            .register(RemoteHealth)
+            .register(LocalCommits)
+            .register(VerifyRemote)
+            .register(Tags)
+            .register(InProgress)
+            .register(DetachedHead)
+            .register(Reflog)
+            .register(Worktrees)
+            .register(Submodules)
+            .register(Branches);
+        checks
+    }
+
+    /// Add a check after the registered ones (a check with the same name is replaced in place)
+    pub fn register(&mut self, check: impl Check + 'static) -> &mut Self {
+        let entry = Entry {
+            enabled: check.enabled_by_default(),
+            check: Arc::new(check),
+        };
+        match self.entries.iter_mut().find(|e| e.check.name() == entry.check.name()) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        self
+    }
+
+    /// Add a check after the registered ones, failing if its name is already taken
+    /// (so a custom check cannot replace a built-in one)
+    pub fn try_register(&mut self, check: impl Check + 'static) -> Result<&mut Self> {
+        if self.entries.iter().any(|e| e.check.name() == check.name()) {
+            anyhow::bail!("Check name already in use: {}", check.name());
+        }
+        Ok(self.register(check))
+    }
+
+    /// Enable or disable a check by name
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        match self.entries.iter_mut().find(|e| e.check.name() == name) {
+            Some(entry) => {
+                entry.enabled = enabled;
+                Ok(())
+            }
+            None => anyhow::bail!(
+                "Unknown check: {} (available: {})",
+                name,
+                self.entries
+                    .iter()
+                    .map(|e| e.check.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Whether a check is registered and enabled
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.entries.iter().any(|e| e.enabled && e.check.name() == name)
+    }
+
+    /// `CORE_CHECKS` that are not enabled
+    pub fn disabled_core(&self) -> Vec<&'static str> {
+        CORE_CHECKS.iter().copied().filter(|name| !self.is_enabled(name)).collect()
+    }
+
+    /// All registered checks with their enabled state
+    pub fn iter(&self) -> impl Iterator<Item = (&dyn Check, bool)> {
+        self.entries.iter().map(|e| (e.check.as_ref(), e.enabled))
+    }
+
+    /// Enabled checks in run order
+    pub fn enabled(&self) -> impl Iterator<Item = &dyn Check> {
+        self.iter().filter(|(_, enabled)| *enabled).map(|(check, _)| check)
+    }
+}
+
+impl Default for Checks {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl fmt::Debug for Checks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|e| (e.check.name(), e.enabled)))
+            .finish()
+    }
+}
+
+/// Check A
+struct Uncommitted;
+
+impl Check for Uncommitted {
+    fn name(&self) -> &str {
+        "uncommitted"
+    }
+    fn description(&self) -> &str {
+        "Uncommitted changes in the working tree or index"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_uncommitted_changes(repo.backend(), repo.path, result, repo.options.ignore_untracked);
+    }
+}
+
+/// Check B
+struct Stash;
+
+impl Check for Stash {
+    fn name(&self) -> &str {
+        "stash"
+    }
+    fn description(&self) -> &str {
+        "Stash entries"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_stash(repo.backend(), repo.path, result);
+    }
+}
+
+/// Refresh remote-tracking refs before Checks C–E (`--fetch`)
+struct Fetch;
+
+impl Check for Fetch {
+    fn name(&self) -> &str {
+        "fetch"
+    }
+    fn description(&self) -> &str {
+        "Run `git fetch --all --prune` first (UNKNOWN if it fails)"
+    }
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::fetch_remotes(repo.path, result, repo.options.fetch_jobs);
+    }
+}
+
+/// Age of the remote tracking refs (`--max-fetch-age`)
+struct FetchAge;
+
+impl Check for FetchAge {
+    fn name(&self) -> &str {
+        "fetch_age"
+    }
+    fn description(&self) -> &str {
+        "Remote tracking refs older than --max-fetch-age (UNKNOWN)"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_fetch_age(repo.backend(), repo.path, result, repo.options.max_fetch_age);
+    }
+}
+
+/// Remotes pointing to missing or unreachable locations
+struct RemoteHealth;
+
+impl Check for RemoteHealth {
+    fn name(&self) -> &str {
+        "remote_health"
+    }
+    fn description(&self) -> &str {
+        "All remotes missing or unreachable (UNKNOWN)"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_remote_health(repo.backend(), repo.path, result, repo.options.probe_remotes);
+    }
+}
+
+/// Checks C and D
+struct LocalCommits;
+
+impl Check for LocalCommits {
+    fn name(&self) -> &str {
+        "local_commits"
+    }
+    fn description(&self) -> &str {
+        "Commits not on any remote (UNKNOWN without remote refs)"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_local_only_commits(repo.backend(), repo.path, result);
+    }
+}
+
+/// Check C against the live remotes (`--verify-remote`)
+struct VerifyRemote;
+
+impl Check for VerifyRemote {
+    fn name(&self) -> &str {
+        "verify_remote"
+    }
+    fn description(&self) -> &str {
+        "Branch tips not on any ref that exists on a remote now (git ls-remote)"
+    }
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_branches_on_remote(repo.backend(), repo.path, result);
+    }
+}
+
+/// Check E
+struct Tags;
+
+impl Check for Tags {
+    fn name(&self) -> &str {
+        "tags"
+    }
+    fn description(&self) -> &str {
+        "Tags pointing at commits not on any remote"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_local_only_tags(repo.backend(), repo.path, result, repo.options.probe_remotes);
+    }
+}
+
+/// Check F
+struct InProgress;
+
+impl Check for InProgress {
+    fn name(&self) -> &str {
+        "in_progress"
+    }
+    fn description(&self) -> &str {
+        "Rebase, merge, cherry-pick, revert or bisect in progress"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_in_progress_operations(repo.backend(), repo.path, result);
+    }
+}
+
+/// Check G
+struct DetachedHead;
+
+impl Check for DetachedHead {
+    fn name(&self) -> &str {
+        "detached_head"
+    }
+    fn description(&self) -> &str {
+        "Commits only reachable from a detached HEAD"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_detached_head_commits(repo.backend(), repo.path, result);
+    }
+}
+
+/// Check G (reflog)
+struct Reflog;
+
+impl Check for Reflog {
+    fn name(&self) -> &str {
+        "reflog"
+    }
+    fn description(&self) -> &str {
+        "Commits only reachable from the reflog"
+    }
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_reflog_only_commits(repo.path, result);
+    }
+}
+
+/// Check H
+struct Worktrees;
+
+impl Check for Worktrees {
+    fn name(&self) -> &str {
+        "worktrees"
+    }
+    fn description(&self) -> &str {
+        "Unsafe linked worktrees"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_worktrees(repo.path, result, repo.options);
+    }
+}
+
+/// Check I
+struct Submodules;
+
+impl Check for Submodules {
+    fn name(&self) -> &str {
+        "submodules"
+    }
+    fn description(&self) -> &str {
+        "Unsafe initialized submodules (checked recursively)"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::check_submodules(repo.path, result, repo.options);
+    }
+}
+
+/// Per-branch breakdown (report only, runs after Check C)
+struct Branches;
+
+impl Check for Branches {
+    fn name(&self) -> &str {
+        "branches"
+    }
+    fn description(&self) -> &str {
+        "List local branches with upstream, ahead/behind and local-only commits (report only)"
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        checker::record_branches(repo.path, result);
+    }
+}
+
+/// External-command check (`[[check]]` in the config file).
+/// The command runs through `sh -c` in the working tree; exit code 0 means
+/// nothing was found, 1 means UNSAFE and anything else UNKNOWN.
+/// Stdout (or stderr if stdout is empty) becomes the reason text.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandCheck {
+    pub name: String,
+    pub command: String,
+    /// Shown by `repo-check checks` (defaults to the command)
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl Check for CommandCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn description(&self) -> &str {
+        self.description.as_deref().unwrap_or(&self.command)
+    }
+    fn run(&self, repo: &Repo, result: &mut RepoResult) {
+        let mut command = Command::new("sh");
+        command.args(["-c", &self.command]).current_dir(repo.path);
+        let output = match checker::run_command(command, &self.command) {
+            Ok(o) => o,
+            Err(e) if e.is::<TimedOut>() => {
+                checker::record_error(result, e);
+                return;
+            }
+            Err(e) => {
+                let message = format!("failed to run `{}`: {}", self.command, e);
+                result.mark_unknown(Reason::Custom {
+                    check: self.name.clone(),
+                    message: message.clone(),
+                });
+                result.errors.push(message);
+                return;
+            }
+        };
+
+        let code = output.status.code();
+        if code == Some(0) {
+            return;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let message = if !stdout.is_empty() {
+            stdout
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            if !stderr.is_empty() {
+                stderr
+            } else {
+                match code {
+                    Some(code) => format!("`{}` exited with status {}", self.command, code),
+                    None => format!("`{}` was terminated by a signal", self.command),
+                }
+            }
+        };
+        let reason = Reason::Custom {
+            check: self.name.clone(),
+            message,
+        };
+        if code == Some(1) {
+            result.mark_unsafe(reason);
+        } else {
+            result.mark_unknown(reason);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::check_repository;
+    use crate::test_util::git;
+    use crate::types::{Reason, Status};
+    use tempfile::TempDir;
+
+    /// Example org-specific rule
+    struct LocalNotes;
+
+    impl Check for LocalNotes {
+        fn name(&self) -> &str {
+            "local_notes"
+        }
+        fn description(&self) -> &str {
+            "A .local-notes/ directory exists"
+        }
+        fn run(&self, repo: &Repo, result: &mut RepoResult) {
+            if repo.path.join(".local-notes").is_dir() {
+                result.mark_unsafe(Reason::Custom {
+                    check: self.name().to_string(),
+                    message: "local notes would be lost".to_string(),
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_enable_disable() {
+        let mut checks = Checks::builtin();
+        assert!(checks.is_enabled("tags"));
+        assert!(!checks.is_enabled("reflog"));
+
+        checks.set_enabled("tags", false).unwrap();
+        checks.set_enabled("reflog", true).unwrap();
+        assert!(!checks.is_enabled("tags"));
+        assert!(checks.is_enabled("reflog"));
+        assert!(checks.set_enabled("nonexistent", true).is_err());
+        assert_eq!(checks.enabled().count(), 11);
+        assert_eq!(checks.disabled_core(), vec!["tags"]);
+        assert!(Checks::builtin().disabled_core().is_empty());
+
+        // Custom checks cannot take over a built-in name
+        let replacement = CommandCheck {
+            name: "local_commits".to_string(),
+            command: "true".to_string(),
+            description: None,
+        };
+        assert!(checks.try_register(replacement).is_err());
+        let (local_commits, _) = checks.iter().find(|(c, _)| c.name() == "local_commits").unwrap();
+        assert_eq!(local_commits.description(), LocalCommits.description());
+    }
+
+    #[test]
+    fn test_custom_check() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init"]);
+        std::fs::write(dir.path().join(".git/info/exclude"), ".local-notes/\n").unwrap();
+        std::fs::create_dir(dir.path().join(".local-notes")).unwrap();
+
+        let mut options = CheckOptions::default();
+        assert_eq!(check_repository(dir.path(), &options).status, Status::Unknown);
+
+        options.checks.register(LocalNotes);
+        let result = check_repository(dir.path(), &options);
+        assert_eq!(result.status, Status::Unsafe);
+        assert!(result
+            .reasons
+            .iter()
+            .any(|r| r.to_string() == "local_notes: local notes would be lost"));
+
+        options.checks.set_enabled("local_notes", false).unwrap();
+        assert_eq!(check_repository(dir.path(), &options).status, Status::Unknown);
+    }
+
+    #[test]
+    fn test_command_check() {
+        let dir = TempDir::new().unwrap();
+        let options = CheckOptions::default();
+        let repo = Repo {
+            path: dir.path(),
+            options: &options,
+        };
+        let check = |command: &str| {
+            let check = CommandCheck {
+                name: "no-local-db".to_string(),
+                command: command.to_string(),
+                description: None,
+            };
+            let mut result = RepoResult::new(dir.path().to_path_buf());
+            check.run(&repo, &mut result);
+            result.finalize_safe();
+            result
+        };
+
+        let result = check("test ! -f data/local.sqlite");
+        assert_eq!(result.status, Status::Safe);
+
+        std::fs::create_dir(dir.path().join("data")).unwrap();
+        std::fs::write(dir.path().join("data/local.sqlite"), "").unwrap();
+        let result = check("test ! -f data/local.sqlite");
+        assert_eq!(result.status, Status::Unsafe);
+        assert_eq!(
+            result.reasons,
+            vec![Reason::Custom {
+                check: "no-local-db".to_string(),
+                message: "`test ! -f data/local.sqlite` exited with status 1".to_string(),
+            }]
+        );
+
+        let result = check("echo 'local database found'; exit 1");
+        assert_eq!(result.reasons[0].to_string(), "no-local-db: local database found");
+
+        let result = check("echo 'cannot tell'; exit 3");
+        assert_eq!(result.status, Status::Unknown);
+    }
+
+    #[test]
+    fn test_command_check_timeout() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init"]);
+
+        let mut options = CheckOptions {
+            timeout: Some(std::time::Duration::from_millis(200)),
+            ..Default::default()
+        };
+        options.checks.register(CommandCheck {
+            name: "slow".to_string(),
+            command: "sleep 5".to_string(),
+            description: None,
+        });
+        let result = check_repository(dir.path(), &options);
+        assert_eq!(result.status, Status::Unknown);
+        assert!(result.reasons.contains(&Reason::Timeout("sleep 5".to_string())));
+    }
+}
@@ -1,14 +1,34 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use repo_check::backend::Backend;
+use repo_check::config::{parse_duration, parse_max_depth};
+use repo_check::journal::JournalAction;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Check if local Git repositories are safe to delete
 #[derive(Parser, Debug)]
 #[command(name = "repo-check")]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Include current directory (./) as a target
     #[arg(long)]
     pub include_dot: bool,
 
+    /// Maximum directory depth to search for repositories (number or "unlimited", default: 1)
+    #[arg(long, value_name = "N", value_parser = parse_max_depth)]
+    pub max_depth: Option<usize>,
+
+    /// Only scan repositories whose path (relative to the target directory) matches GLOB (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip paths (relative to the target directory) matching GLOB (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
     /// Show only SAFE repositories
     #[arg(long, conflicts_with_all = ["only_unsafe", "only_unknown"])]
     pub only_safe: bool,
@@ -26,9 +46,58 @@ pub struct Args {
     pub json: bool,
 
     /// Ignore untracked files when checking for uncommitted changes
-    #[arg(long)]
+    #[arg(long, overrides_with = "no_ignore_untracked")]
     pub ignore_untracked: bool,
 
+    /// Check untracked files even if the config file sets `ignore_untracked`
+    #[arg(long, overrides_with = "ignore_untracked")]
+    pub no_ignore_untracked: bool,
+
+    /// Also treat commits only reachable from the reflog as local-only work
+    #[arg(long)]
+    pub check_reflog: bool,
+
+    /// Run `git fetch --all --prune` in each repository before checking it
+    /// (repositories whose fetch fails are UNKNOWN)
+    #[arg(long)]
+    pub fetch: bool,
+
+    /// Maximum number of repositories fetched at the same time (0: no limit)
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    pub fetch_jobs: usize,
+
+    /// Check every local branch against the refs that exist on the remotes now
+    /// (`git ls-remote`) instead of trusting remote tracking refs alone
+    #[arg(long)]
+    pub verify_remote: bool,
+
+    /// Also contact network remotes (`git ls-remote`) when checking whether
+    /// remotes are reachable and have every tag (local path remotes are always checked)
+    #[arg(long)]
+    pub probe_remotes: bool,
+
+    /// Report repositories whose remote tracking refs were last updated longer
+    /// ago than this as UNKNOWN (e.g. 14d)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub max_fetch_age: Option<Duration>,
+
+    /// How checks read repositories: git (spawn git) or gix (in-process, needs the gix feature)
+    #[arg(long, value_name = "BACKEND", default_value = "git")]
+    pub backend: Backend,
+
+    /// Kill git commands running longer than this and report the repository as UNKNOWN
+    /// (e.g. 30s, 5m; 0 disables; default: 60s)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Enable checks by name (see `repo-check checks`)
+    #[arg(long, value_name = "NAME", value_delimiter = ',', global = true)]
+    pub enable: Vec<String>,
+
+    /// Disable checks by name (see `repo-check checks`)
+    #[arg(long, value_name = "NAME", value_delimiter = ',', global = true)]
+    pub disable: Vec<String>,
+
     /// Include UNKNOWN repositories in delete candidates
     #[arg(long, requires = "delete")]
     pub allow_unknown: bool,
@@ -42,14 +111,155 @@ pub struct Args {
     pub yes: bool,
 
     /// Move to trash instead of permanent deletion (falls back to rm -rf if unavailable)
-    #[arg(long, requires = "delete")]
+    #[arg(long, requires = "delete", conflicts_with = "quarantine", overrides_with = "no_trash")]
     pub trash: bool,
 
+    /// Delete permanently even if the config file sets `trash`
+    #[arg(long, requires = "delete", overrides_with = "trash")]
+    pub no_trash: bool,
+
+    /// Move to the quarantine directory instead of deleting (see `restore` / `purge`)
+    #[arg(long, requires = "delete")]
+    pub quarantine: bool,
+
+    /// Quarantine directory (default: $XDG_DATA_HOME/repo-check/quarantine)
+    #[arg(long, value_name = "DIR", global = true)]
+    pub quarantine_dir: Option<PathBuf>,
+
+    /// Write a verified git bundle of each repository into DIR before deleting it
+    #[arg(long, value_name = "DIR", requires = "delete")]
+    pub bundle_dir: Option<PathBuf>,
+
+    /// Write the re-clone manifest of deleted repositories to FILE
+    /// (default: $XDG_DATA_HOME/repo-check/manifests/reclone-<timestamp>.json)
+    #[arg(long, value_name = "FILE", requires = "delete")]
+    pub manifest: Option<PathBuf>,
+
     /// Show what would be deleted without actually deleting
     #[arg(long, requires = "delete")]
     pub dry_run: bool,
 
-    /// Target directory to scan (defaults to current directory)
-    #[arg(default_value = ".")]
-    pub path: String,
+    /// Deletion journal file (default: $XDG_DATA_HOME/repo-check/journal.jsonl)
+    #[arg(long, value_name = "FILE", global = true)]
+    pub journal: Option<PathBuf>,
+
+    /// Use this config file instead of the user-wide and per-directory ones
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Target directories to scan (defaults to configured roots, then the current directory)
+    pub paths: Vec<PathBuf>,
+}
+
+impl Args {
+    /// --ignore-untracked / --no-ignore-untracked (None: neither was given)
+    pub fn ignore_untracked(&self) -> Option<bool> {
+        flag(self.ignore_untracked, self.no_ignore_untracked)
+    }
+
+    /// --trash / --no-trash (None: neither was given)
+    pub fn trash(&self) -> Option<bool> {
+        flag(self.trash, self.no_trash)
+    }
+}
+
+/// A `--x` / `--no-x` pair; clap resets the earlier one, so the last one given wins
+fn flag(set: bool, unset: bool) -> Option<bool> {
+    match (set, unset) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Subcommands operating on the quarantine directory, journal, manifests and checks
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Move quarantined repositories back to their original location
+    Restore {
+        /// Quarantine entry id or original directory name
+        #[arg(required_unless_present = "all")]
+        name: Option<String>,
+
+        /// Restore all quarantined repositories
+        #[arg(long, conflicts_with = "name")]
+        all: bool,
+    },
+    /// Permanently remove old quarantined repositories
+    Purge {
+        /// Only purge entries quarantined longer ago than this (e.g. 30d, 12h)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        older_than: Duration,
+
+        /// Show what would be purged without removing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Clone deleted repositories back from a re-clone manifest
+    Reclone {
+        /// Manifest written by delete mode
+        manifest: PathBuf,
+
+        /// Show what would be cloned without cloning
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the deletion journal
+    History {
+        /// Only show entries newer than this (e.g. 7d, 12h)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        since: Option<Duration>,
+
+        /// Only show deleted or skipped repositories
+        #[arg(long, value_enum)]
+        action: Option<HistoryAction>,
+
+        /// Only show repositories whose path contains this string
+        #[arg(long, value_name = "SUBSTRING")]
+        path: Option<String>,
+
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// List available checks and whether they are enabled
+    Checks,
+}
+
+/// `history --action` values
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum HistoryAction {
+    Deleted,
+    Skipped,
+}
+
+impl From<HistoryAction> for JournalAction {
+    fn from(action: HistoryAction) -> Self {
+        match action {
+            HistoryAction::Deleted => JournalAction::Deleted,
+            HistoryAction::Skipped => JournalAction::Skipped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negated_flags() {
+        let args = Args::try_parse_from(["repo-check"]).unwrap();
+        assert_eq!(args.ignore_untracked(), None);
+        assert_eq!(args.trash(), None);
+
+        let args = Args::try_parse_from(["repo-check", "--no-ignore-untracked", "--delete", "--no-trash"]).unwrap();
+        assert_eq!(args.ignore_untracked(), Some(false));
+        assert_eq!(args.trash(), Some(false));
+
+        // The last one given wins
+        let args = Args::try_parse_from(["repo-check", "--ignore-untracked", "--no-ignore-untracked"]).unwrap();
+        assert_eq!(args.ignore_untracked(), Some(false));
+        let args = Args::try_parse_from(["repo-check", "--delete", "--no-trash", "--trash"]).unwrap();
+        assert_eq!(args.trash(), Some(true));
+    }
 }
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use colored::Colorize;
+use repo_check::{quarantine, reclone};
+use std::path::Path;
+use std::time::Duration;
+
+/// Print the outcome of a step started with `print!("<step>... ")`;
+/// returns whether it succeeded
+fn finish<T>(result: Result<T>) -> bool {
+    match result {
+        Ok(_) => {
+            println!("{}", "done".green());
+            true
+        }
+        Err(e) => {
+            println!("{}: {}", "failed".red(), e);
+            false
+        }
+    }
+}
+
+/// `repo-check restore`: the entry matching `name`, or all entries
+pub fn restore(quarantine_dir: &Path, name: Option<&str>) -> Result<()> {
+    let (mut restored, mut failed) = (0, 0);
+    for entry in quarantine::select_entries(quarantine_dir, name)? {
+        print!("Restoring {}... ", entry.manifest.original_path.display());
+        if finish(quarantine::restore_entry(&entry)) {
+            restored += 1;
+        } else {
+            failed += 1;
+        }
+    }
+    println!("\nRestored: {}, Failed: {}", restored, failed);
+    Ok(())
+}
+
+/// `repo-check purge`
+pub fn purge(quarantine_dir: &Path, older_than: Duration, dry_run: bool) -> Result<()> {
+    let mut purged = 0;
+    for entry in quarantine::expired_entries(quarantine_dir, older_than)? {
+        if dry_run {
+            println!("Would purge {} ({})", entry.id(), entry.manifest.original_path.display());
+            purged += 1;
+            continue;
+        }
+        print!("Purging {} ({})... ", entry.id(), entry.manifest.original_path.display());
+        if finish(quarantine::purge_entry(&entry)) {
+            purged += 1;
+        }
+    }
+    println!("\nPurged: {}", purged);
+    Ok(())
+}
+
+/// `repo-check reclone`
+pub fn reclone(manifest_path: &Path, dry_run: bool) -> Result<()> {
+    let manifest = reclone::read_manifest(manifest_path)?;
+    let (mut cloned, mut failed) = (0, 0);
+    for entry in &manifest.repositories {
+        if dry_run {
+            let url = entry
+                .primary_remote()
+                .map(|r| entry.remote_url(r).to_string_lossy().to_string())
+                .unwrap_or_else(|| "-".to_string());
+            println!("Would clone {} -> {}", url, entry.path.display());
+            cloned += 1;
+            continue;
+        }
+        print!("Cloning {}... ", entry.path.display());
+        let result = reclone::reclone_entry(entry);
+        let moved_on = result.as_ref().ok().cloned().flatten();
+        if finish(result) {
+            cloned += 1;
+        } else {
+            failed += 1;
+        }
+        // The remote may have moved on since deletion
+        if let (Some(current), Some(head)) = (moved_on, &entry.snapshot.head) {
+            eprintln!(
+                "{}: {} is at {} (was {} when deleted)",
+                "Warning".yellow(),
+                entry.path.display(),
+                current,
+                head
+            );
+        }
+    }
+    println!("\nCloned: {}, Failed: {}", cloned, failed);
+    Ok(())
+}
@@ -0,0 +1,363 @@
+use crate::checks::CommandCheck;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Per-directory configuration file name
+pub const CONFIG_FILE_NAME: &str = ".repo-check.toml";
+
+/// Configuration loaded from a TOML file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Directories to scan when no path is given on the command line
+    pub roots: Vec<PathBuf>,
+    /// Maximum directory depth to search
+    pub max_depth: Option<MaxDepth>,
+    /// Only scan repositories matching these glob patterns
+    pub include: Vec<String>,
+    /// Never scan paths matching these glob patterns
+    pub exclude: Vec<String>,
+    /// Directory names not descended into (replaces the default list)
+    pub skipped_dirs: Option<Vec<String>>,
+    /// Ignore untracked files when checking for uncommitted changes
+    pub ignore_untracked: Option<bool>,
+    /// Move to trash instead of permanent deletion
+    pub trash: Option<bool>,
+    /// Directory quarantined repositories are moved to
+    pub quarantine_dir: Option<PathBuf>,
+    /// Deletion journal file
+    pub journal: Option<PathBuf>,
+    /// Per-command timeout, e.g. "30s" ("0" disables)
+    pub timeout: Option<String>,
+    /// Remote tracking refs older than this make a repository UNKNOWN, e.g. "14d"
+    pub max_fetch_age: Option<String>,
+    /// Repositories that are never offered for deletion
+    pub protected: Vec<PathBuf>,
+    /// Enable or disable individual checks by name
+    pub checks: BTreeMap<String, bool>,
+    /// External-command checks (`[[check]]` tables)
+    pub check: Vec<CommandCheck>,
+}
+
+/// `max_depth` as written in the config file (a number or "unlimited")
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum MaxDepth {
+    Number(usize),
+    Keyword(String),
+}
+
+impl MaxDepth {
+    fn resolve(&self) -> Result<usize> {
+        let value = match self {
+            MaxDepth::Number(n) => n.to_string(),
+            MaxDepth::Keyword(s) => s.clone(),
+        };
+        parse_max_depth(&value).map_err(|e| anyhow::anyhow!("Invalid max_depth: {}", e))
+    }
+}
+
+/// Parse --max-depth / `max_depth` ("unlimited" maps to usize::MAX)
+pub fn parse_max_depth(value: &str) -> Result<usize, String> {
+    if value == "unlimited" {
+        return Ok(usize::MAX);
+    }
+    match value.parse::<usize>() {
+        Ok(0) => Err("depth must be at least 1".to_string()),
+        Ok(depth) => Ok(depth),
+        Err(_) => Err(format!("invalid depth: {}", value)),
+    }
+}
+
+/// Parse a duration such as "30d", "12h", "2w" (s/m/h/d/w units). The unit is
+/// required, only "0" may be given without one.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: {}", value))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        "" if number == 0 => 0,
+        "" => return Err(format!("missing duration unit (s/m/h/d/w): {}", value)),
+        _ => return Err(format!("invalid duration unit: {}", unit)),
+    };
+    number
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration too large: {}", value))
+}
+
+/// Expand a leading `~` and resolve relative paths against `base`
+fn resolve_path(path: &Path, base: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = env::var_os("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    base.join(path)
+}
+
+/// Location of the user-wide config file (`$XDG_CONFIG_HOME/repo-check/config.toml`)
+fn global_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("repo-check").join("config.toml"))
+}
+
+/// Directory for data written by repo-check (`$XDG_DATA_HOME/repo-check`,
+/// defaults to `~/.local/share/repo-check`)
+pub fn data_dir() -> PathBuf {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    data_home.join("repo-check")
+}
+
+impl Config {
+    /// Load configuration from a file.
+    /// Relative paths (`roots`, `protected`, `quarantine_dir`, `journal`) are resolved against the file's directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read config: {}", path.display()))?;
+        let mut config: Self = toml::from_str(&content)
+            .context(format!("Failed to parse config: {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        config.roots = config.roots.iter().map(|p| resolve_path(p, base)).collect();
+        config.protected = config.protected.iter().map(|p| resolve_path(p, base)).collect();
+        config.quarantine_dir = config.quarantine_dir.map(|p| resolve_path(&p, base));
+        config.journal = config.journal.map(|p| resolve_path(&p, base));
+        // Fail early on an invalid depth or duration
+        if let Some(depth) = &config.max_depth {
+            depth.resolve().context(format!("Invalid config: {}", path.display()))?;
+        }
+        let durations = [("timeout", &config.timeout), ("max_fetch_age", &config.max_fetch_age)];
+        for (key, value) in durations {
+            if let Some(value) = value {
+                parse_duration(value)
+                    .map_err(|e| anyhow::anyhow!("Invalid {}: {}", key, e))
+                    .context(format!("Invalid config: {}", path.display()))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load the user-wide config (if there is one)
+    pub fn load_user() -> Result<Self> {
+        match global_config_path().filter(|p| p.is_file()) {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load `.repo-check.toml` from a scan target directory (if there is one).
+    /// The file may come with an untrusted checkout and only applies to this
+    /// target, so `[[check]]` commands and the settings shared by all targets
+    /// (`roots`, `quarantine_dir`, `journal`) are dropped with a warning.
+    pub fn load_target(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(Self::load(&path)?.target_only(&path)))
+    }
+
+    fn target_only(mut self, path: &Path) -> Self {
+        let mut ignored = Vec::new();
+        if !self.check.is_empty() {
+            ignored.push("[[check]]");
+            self.check.clear();
+        }
+        if !self.roots.is_empty() {
+            ignored.push("roots");
+            self.roots.clear();
+        }
+        if self.quarantine_dir.take().is_some() {
+            ignored.push("quarantine_dir");
+        }
+        if self.journal.take().is_some() {
+            ignored.push("journal");
+        }
+        if !ignored.is_empty() {
+            eprintln!(
+                "{}: Ignoring {} in {} (only allowed in the user config or --config)",
+                "Warning".yellow(),
+                ignored.join(", "),
+                path.display()
+            );
+        }
+        self
+    }
+
+    /// Layer `other` on top of `self`: scalars are overridden, pattern and
+    /// protected lists are combined, roots are replaced when set
+    pub fn merge(self, other: Config) -> Self {
+        Self {
+            roots: if other.roots.is_empty() { self.roots } else { other.roots },
+            max_depth: other.max_depth.or(self.max_depth),
+            include: [self.include, other.include].concat(),
+            exclude: [self.exclude, other.exclude].concat(),
+            skipped_dirs: other.skipped_dirs.or(self.skipped_dirs),
+            ignore_untracked: other.ignore_untracked.or(self.ignore_untracked),
+            trash: other.trash.or(self.trash),
+            quarantine_dir: other.quarantine_dir.or(self.quarantine_dir),
+            journal: other.journal.or(self.journal),
+            timeout: other.timeout.or(self.timeout),
+            max_fetch_age: other.max_fetch_age.or(self.max_fetch_age),
+            protected: [self.protected, other.protected].concat(),
+            checks: self.checks.into_iter().chain(other.checks).collect(),
+            check: [self.check, other.check].concat(),
+        }
+    }
+
+    /// Effective max depth (validated in `load`)
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth.as_ref().and_then(|d| d.resolve().ok())
+    }
+
+    /// Effective timeout (validated in `load`)
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.as_deref().and_then(|t| parse_duration(t).ok())
+    }
+
+    /// Effective maximum fetch age (validated in `load`)
+    pub fn max_fetch_age(&self) -> Option<Duration> {
+        self.max_fetch_age.as_deref().and_then(|t| parse_duration(t).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        fs::write(
+            &path,
+            r#"
+roots = ["src"]
+max_depth = "unlimited"
+include = ["github.com/*/*"]
+exclude = ["vendor/*"]
+skipped_dirs = ["node_modules"]
+ignore_untracked = true
+timeout = "30s"
+max_fetch_age = "2w"
+protected = ["src/critical"]
+
+[checks]
+reflog = true
+submodules = false
+
+[[check]]
+name = "no-local-db"
+command = "test ! -f data/local.sqlite"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.roots, vec![dir.path().join("src")]);
+        assert_eq!(config.max_depth(), Some(usize::MAX));
+        assert_eq!(config.include, vec!["github.com/*/*"]);
+        assert_eq!(config.exclude, vec!["vendor/*"]);
+        assert_eq!(config.skipped_dirs, Some(vec!["node_modules".to_string()]));
+        assert_eq!(config.ignore_untracked, Some(true));
+        assert_eq!(config.trash, None);
+        assert_eq!(config.timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(config.max_fetch_age(), Some(Duration::from_secs(14 * 86400)));
+        assert_eq!(config.protected, vec![dir.path().join("src/critical")]);
+        assert_eq!(config.checks.get("reflog"), Some(&true));
+        assert_eq!(config.checks.get("submodules"), Some(&false));
+        assert_eq!(config.check.len(), 1);
+        assert_eq!(config.check[0].name, "no-local-db");
+    }
+
+    #[test]
+    fn test_invalid_config_rejected() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+
+        fs::write(&path, "exclud = [\"vendor/*\"]\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        fs::write(&path, "max_depth = 0\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        fs::write(&path, "timeout = \"soon\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        fs::write(&path, "max_fetch_age = \"14x\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        fs::write(&path, "[checks]\nreflog = \"yes\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_load_target() {
+        let dir = TempDir::new().unwrap();
+        assert!(Config::load_target(dir.path()).unwrap().is_none());
+
+        fs::write(
+            dir.path().join(CONFIG_FILE_NAME),
+            "roots = [\"/\"]\njournal = \"j.jsonl\"\nexclude = [\"vendor/*\"]\n[[check]]\nname = \"x\"\ncommand = \"rm -rf ~\"\n",
+        )
+        .unwrap();
+        let config = Config::load_target(dir.path()).unwrap().unwrap();
+        assert!(config.check.is_empty());
+        assert!(config.roots.is_empty());
+        assert_eq!(config.journal, None);
+        assert_eq!(config.exclude, vec!["vendor/*"]);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 86400)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::from_secs(14 * 86400)));
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("18446744073709551615w").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let global: Config = toml::from_str(
+            "roots = [\"/a\"]\nmax_depth = 2\nexclude = [\"vendor/*\"]\ntrash = true\n[checks]\ntags = false\n",
+        )
+        .unwrap();
+        let local: Config =
+            toml::from_str("max_depth = 3\nexclude = [\"tmp/*\"]\n[checks]\nreflog = true\n").unwrap();
+
+        let config = global.merge(local);
+        assert_eq!(config.roots, vec![PathBuf::from("/a")]);
+        assert_eq!(config.max_depth(), Some(3));
+        assert_eq!(config.exclude, vec!["vendor/*", "tmp/*"]);
+        assert_eq!(config.trash, Some(true));
+        assert_eq!(config.checks.get("tags"), Some(&false));
+        assert_eq!(config.checks.get("reflog"), Some(&true));
+    }
+}
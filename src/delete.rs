@@ -1,12 +1,16 @@
+use crate::bundle;
 use crate::checker;
-use crate::types::{DeleteConfirm, RepoResult, Status};
+use crate::interrupt;
+use crate::journal::{self, JournalAction, JournalEntry, RemovalMethod, RepoSnapshot};
+use crate::quarantine;
+use crate::reclone::{self, ManifestEntry};
+use crate::types::{DeleteCandidate, DeleteConfirm, DeleteMethod, DeleteOptions, Reason, RepoResult, Status};
 use anyhow::Result;
-use colored::Colorize;
-use dialoguer::{theme::ColorfulTheme, Select};
 use std::fs;
 use std::path::Path;
 
-/// Filter repositories that are candidates for deletion
+/// Filter repositories that are candidates for deletion (protected repositories
+/// and repositories whose checks timed out, even with `allow_unknown`, never are)
 pub fn get_delete_candidates(
     results: &[RepoResult],
     allow_unknown: bool,
@@ -16,150 +20,200 @@ pub fn get_delete_candidates(
         .filter(|r| {
             r.status == Status::Safe || (allow_unknown && r.status == Status::Unknown)
         })
+        .filter(|r| r.protected.is_none())
+        // The remaining checks were skipped, so nothing is known about the repository
+        .filter(|r| !r.reasons.iter().any(|reason| matches!(reason, Reason::Timeout(_))))
         .collect()
 }
 
-/// Delete a repository (prefer trash, fallback to rm -rf)
-fn delete_repository(path: &Path, use_trash: bool, skip_confirm: bool) -> Result<bool> {
-    if use_trash {
+/// Progress of `execute_delete`, for the caller to display
+#[derive(Debug)]
+pub enum DeleteEvent<'a> {
+    /// A verified bundle was written
+    Bundled { path: &'a Path, bundle: &'a Path },
+    /// Removal starts (followed by `Removed` or `Skipped`)
+    Removing { path: &'a Path, method: &'a DeleteMethod },
+    Removed { path: &'a Path, method: RemovalMethod },
+    /// The repository is kept; `reason` is also recorded in the journal
+    Skipped { path: &'a Path, reason: &'a str },
+    /// The re-clone manifest was written
+    ManifestWritten(&'a Path),
+    /// Something went wrong that does not affect the outcome (e.g. the journal
+    /// could not be written)
+    Warning(String),
+}
+
+/// Decisions and output of `execute_delete` are left to the caller
+pub trait DeleteHandler {
+    /// Whether to delete `path` (not asked with `DeleteOptions::skip_confirm`
+    /// or after `DeleteConfirm::All`)
+    fn confirm(&mut self, path: &Path) -> DeleteConfirm;
+    /// Whether to remove `path` permanently after moving it to the trash failed
+    /// (without confirmations the repository is skipped instead)
+    fn confirm_permanent(&mut self, path: &Path, error: &dyn std::error::Error) -> bool;
+    fn report(&mut self, event: DeleteEvent);
+}
+
+/// Delete a repository using the given method (trash falls back to rm -rf
+/// if `confirm` is set and the handler agrees). Returns the method actually
+/// used, or why the repository was skipped.
+fn delete_repository(
+    result: &RepoResult,
+    method: &DeleteMethod,
+    confirm: bool,
+    handler: &mut dyn DeleteHandler,
+) -> Result<Result<RemovalMethod, String>> {
+    let path = &result.path;
+    if let DeleteMethod::Quarantine(quarantine_dir) = method {
+        let quarantined = quarantine::quarantine_repository(result, quarantine_dir)?;
+        // The entry holds the complete copy: keep it and leave the rest to the user
+        if let Some(e) = quarantined.leftover {
+            handler.report(DeleteEvent::Warning(format!(
+                "Quarantined {}, but could not remove all of the original ({}); remove the leftovers manually",
+                path.display(),
+                e
+            )));
+        }
+        return Ok(Ok(RemovalMethod::Quarantine));
+    }
+
+    if *method == DeleteMethod::Trash {
         match trash::delete(path) {
-            Ok(()) => return Ok(true),
+            Ok(()) => return Ok(Ok(RemovalMethod::Trash)),
             Err(e) => {
-                if skip_confirm {
-                    eprintln!(
-                        "{}: Failed to move to trash ({}), skipping (use without --trash to force rm -rf)",
-                        "Warning".yellow(),
-                        e
-                    );
-                    return Ok(false);
-                }
-                eprintln!(
-                    "{}: Failed to move to trash: {}",
-                    "Warning".yellow(),
-                    e
-                );
-                let options = &["Yes, use rm -rf instead", "No, skip this repository"];
-                let selection = Select::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Fall back to permanent deletion?")
-                    .items(options)
-                    .default(1)
-                    .interact_opt();
-                match selection {
-                    Ok(Some(0)) => {} // fall through to rm -rf
-                    _ => return Ok(false),
+                if !(confirm && handler.confirm_permanent(path, &e)) {
+                    return Ok(Err(format!("Failed to move to trash: {}", e)));
                 }
             }
         }
     }
 
     fs::remove_dir_all(path)?;
-    Ok(true)
+    Ok(Ok(RemovalMethod::Rm))
 }
 
-/// Ask user for deletion confirmation
-fn ask_confirmation(path: &Path) -> DeleteConfirm {
-    let path_str = path.display().to_string();
-    println!("\nDelete {}?", path_str.bold());
-
-    let options = &["Yes", "No", "All (delete all remaining)", "Quit"];
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .items(options)
-        .default(1) // Default is "No"
-        .interact_opt();
-
-    match selection {
-        Ok(Some(0)) => DeleteConfirm::Yes,
-        Ok(Some(1)) => DeleteConfirm::No,
-        Ok(Some(2)) => DeleteConfirm::All,
-        Ok(Some(3)) => DeleteConfirm::Quit,
-        _ => DeleteConfirm::No,
+/// Append a journal entry (failures are reported but do not stop deletion)
+fn record(options: &DeleteOptions, handler: &mut dyn DeleteHandler, entry: JournalEntry) {
+    if let Some(journal_path) = &options.journal_path {
+        if let Err(e) = journal::append(journal_path, &entry) {
+            handler.report(DeleteEvent::Warning(format!("Failed to write journal: {}", e)));
+        }
     }
 }
 
-/// Execute deletion
+/// Execute deletion. Stops early when interrupted or on `DeleteConfirm::Quit`.
 pub fn execute_delete(
-    candidates: &[&RepoResult],
-    use_trash: bool,
-    skip_confirm: bool,
+    candidates: &[DeleteCandidate],
+    options: &DeleteOptions,
+    handler: &mut dyn DeleteHandler,
 ) -> Result<(usize, usize)> {
     let mut deleted = 0;
     let mut skipped = 0;
-    let mut delete_all = skip_confirm;
+    let mut delete_all = options.skip_confirm;
+    let mut manifest_entries = Vec::new();
 
-    for result in candidates {
+    for &DeleteCandidate { result, check_options, method } in candidates {
+        let planned_method = match method {
+            DeleteMethod::Remove => RemovalMethod::Rm,
+            DeleteMethod::Trash => RemovalMethod::Trash,
+            DeleteMethod::Quarantine(_) => RemovalMethod::Quarantine,
+        };
+        if interrupt::is_requested() {
+            break;
+        }
         let path = &result.path;
+        // Captured up front: the repository is gone once deleted. A hung
+        // repository is cut off by the same timeout as the checks.
+        let snapshot = {
+            let _timeout = checker::TimeoutScope::new(check_options.timeout);
+            RepoSnapshot::collect(path)
+        };
+        let mut skip = |handler: &mut dyn DeleteHandler, reason: &str| {
+            handler.report(DeleteEvent::Skipped { path, reason });
+            record(
+                options,
+                handler,
+                JournalEntry::new(result, &snapshot, JournalAction::Skipped, planned_method, Some(reason)),
+            );
+            skipped += 1;
+        };
 
         if !delete_all {
-            match ask_confirmation(path) {
+            match handler.confirm(path) {
                 DeleteConfirm::Yes => {}
                 DeleteConfirm::No => {
-                    skipped += 1;
+                    skip(handler, "Declined by user");
                     continue;
                 }
                 DeleteConfirm::All => {
                     delete_all = true;
                 }
-                DeleteConfirm::Quit => {
-                    println!("Aborted.");
-                    break;
-                }
+                DeleteConfirm::Quit => break,
             }
         }
 
-        // TOCTOU mitigation: recheck before deletion
-        if !checker::quick_recheck(path) {
-            println!(
-                "{}: Repository state changed since scan, skipping: {}",
-                "Warning".yellow(),
-                path.display()
-            );
-            skipped += 1;
+        // TOCTOU mitigation: re-run all checks and compare with the scan
+        let fresh = checker::check_repository(path, check_options);
+        if let Some(protection) = fresh.protected {
+            skip(handler, &format!("Protected ({})", protection));
+            continue;
+        }
+        let changes = result.diff(&fresh);
+        if !changes.is_empty() {
+            skip(handler, &format!("Repository state changed since scan: {}", changes.join("; ")));
             continue;
         }
 
+        // Backup to a git bundle (never delete without a verified bundle)
+        if let Some(bundle_dir) = &options.bundle_dir {
+            match bundle::create_bundle(path, bundle_dir) {
+                Ok(bundle) => handler.report(DeleteEvent::Bundled { path, bundle: &bundle }),
+                Err(e) => {
+                    skip(handler, &format!("Failed to create bundle: {}", e));
+                    continue;
+                }
+            }
+        }
+
         // Execute deletion
-        print!("Deleting {}... ", path.display());
-        match delete_repository(path, use_trash, delete_all) {
-            Ok(true) => {
-                println!("{}", "done".green());
+        handler.report(DeleteEvent::Removing { path, method });
+        match delete_repository(result, method, !delete_all, handler) {
+            Ok(Ok(method)) => {
+                handler.report(DeleteEvent::Removed { path, method });
+                record(
+                    options,
+                    handler,
+                    JournalEntry::new(result, &snapshot, JournalAction::Deleted, method, None),
+                );
+                manifest_entries.push(ManifestEntry {
+                    path: path.clone(),
+                    snapshot,
+                });
                 deleted += 1;
             }
-            Ok(false) => {
-                skipped += 1;
-            }
-            Err(e) => {
-                println!("{}: {}", "failed".red(), e);
-                skipped += 1;
-            }
+            Ok(Err(reason)) => skip(handler, &reason),
+            Err(e) => skip(handler, &format!("Deletion failed: {}", e)),
         }
     }
 
-    Ok((deleted, skipped))
-}
-
-/// Display deletion candidates
-pub fn show_delete_candidates(candidates: &[&RepoResult]) {
-    if candidates.is_empty() {
-        println!("No repositories to delete.");
-        return;
+    // Re-clone manifest of everything that was deleted
+    if let Some(manifest_path) = &options.manifest_path {
+        if !manifest_entries.is_empty() {
+            match reclone::write_manifest(manifest_path, manifest_entries) {
+                Ok(()) => handler.report(DeleteEvent::ManifestWritten(manifest_path)),
+                Err(e) => handler.report(DeleteEvent::Warning(format!("Failed to write re-clone manifest: {}", e))),
+            }
+        }
     }
 
-    println!("The following repositories will be deleted:\n");
-    for result in candidates {
-        let status_str = match result.status {
-            Status::Safe => "SAFE".green(),
-            Status::Unknown => "UNKNOWN".yellow(),
-            _ => "?".normal(),
-        };
-        println!("  {} [{}]", result.path.display(), status_str);
-    }
-    println!("\nTotal: {} repositories", candidates.len());
+    Ok((deleted, skipped))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Reason, RepoResult, Status};
+    use crate::test_util::git;
+    use crate::types::{Protection, RepoResult};
     use std::path::PathBuf;
 
     fn make_result(path: &str, status: Status, reason: Reason) -> RepoResult {
@@ -190,8 +244,118 @@ mod tests {
             make_result("/safe", Status::Safe, Reason::AllChecksOk),
             make_result("/unsafe", Status::Unsafe, Reason::UncommittedChanges),
             make_result("/unknown", Status::Unknown, Reason::NoRemoteRefs),
+            make_result("/timed-out", Status::Unknown, Reason::Timeout("git status".into())),
         ];
         let candidates = get_delete_candidates(&results, true);
         assert_eq!(candidates.len(), 2);
     }
+
+    #[test]
+    fn test_get_delete_candidates_protected() {
+        let mut critical = make_result("/critical", Status::Safe, Reason::AllChecksOk);
+        critical.protected = Some(Protection::MarkerFile);
+        let results = vec![make_result("/safe", Status::Safe, Reason::AllChecksOk), critical];
+        let candidates = get_delete_candidates(&results, true);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].path, PathBuf::from("/safe"));
+    }
+
+    /// Answers every confirmation with `answer` and keeps the skip reasons
+    struct Answer {
+        answer: DeleteConfirm,
+        asked: usize,
+        skipped: Vec<String>,
+    }
+
+    impl DeleteHandler for Answer {
+        fn confirm(&mut self, _path: &Path) -> DeleteConfirm {
+            self.asked += 1;
+            self.answer
+        }
+        fn confirm_permanent(&mut self, _path: &Path, _error: &dyn std::error::Error) -> bool {
+            false
+        }
+        fn report(&mut self, event: DeleteEvent) {
+            if let DeleteEvent::Skipped { reason, .. } = event {
+                self.skipped.push(reason.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_delete_writes_journal() {
+        let base = tempfile::TempDir::new().unwrap();
+        let repo = base.path().join("repo");
+        let dirty = base.path().join("dirty");
+        let kept = base.path().join("kept");
+        for path in [&repo, &dirty, &kept] {
+            std::fs::create_dir(path).unwrap();
+            git(path, &["init"]);
+        }
+
+        let check_options = crate::types::CheckOptions::default();
+        let results = [
+            checker::check_repository(&repo, &check_options),
+            checker::check_repository(&dirty, &check_options),
+            checker::check_repository(&kept, &check_options),
+        ];
+        // Modified after the scan: skipped by the recheck
+        std::fs::write(dirty.join("wip.txt"), "wip").unwrap();
+        // Protected after the scan: never deleted
+        std::fs::write(kept.join(".git").join(checker::PROTECT_MARKER_FILE), "").unwrap();
+
+        let candidates: Vec<DeleteCandidate> = results
+            .iter()
+            .map(|result| DeleteCandidate {
+                result,
+                check_options: &check_options,
+                method: &DeleteMethod::Remove,
+            })
+            .collect();
+        let journal_path = base.path().join("journal.jsonl");
+        let manifest_path = base.path().join("manifest.json");
+        let options = DeleteOptions {
+            journal_path: Some(journal_path.clone()),
+            manifest_path: Some(manifest_path.clone()),
+            ..Default::default()
+        };
+
+        // "All" is only asked for once
+        let mut handler = Answer {
+            answer: DeleteConfirm::All,
+            asked: 0,
+            skipped: Vec::new(),
+        };
+        assert_eq!(execute_delete(&candidates, &options, &mut handler).unwrap(), (1, 2));
+        assert_eq!(handler.asked, 1);
+        assert_eq!(handler.skipped.len(), 2);
+        assert!(!repo.exists());
+        assert!(dirty.exists());
+        assert!(kept.exists());
+
+        let entries = journal::read(&journal_path).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].action, JournalAction::Deleted);
+        assert_eq!(entries[0].method, RemovalMethod::Rm);
+        assert_eq!(entries[0].path, repo);
+        assert_eq!(entries[1].action, JournalAction::Skipped);
+        assert!(entries[1].skip_reason.as_deref().unwrap().contains("Dirty files: 0 -> 1"));
+        assert_eq!(entries[2].skip_reason.as_deref(), Some("Protected (marker file)"));
+
+        // Only the deleted repository is in the re-clone manifest
+        let manifest = reclone::read_manifest(&manifest_path).unwrap();
+        assert_eq!(manifest.repositories.len(), 1);
+        assert_eq!(manifest.repositories[0].path, repo);
+
+        // Declined repositories are kept and journaled
+        let mut handler = Answer {
+            answer: DeleteConfirm::No,
+            asked: 0,
+            skipped: Vec::new(),
+        };
+        assert_eq!(execute_delete(&candidates[1..], &options, &mut handler).unwrap(), (0, 2));
+        assert_eq!(handler.skipped, vec!["Declined by user"; 2]);
+        assert!(dirty.exists());
+        assert_eq!(journal::read(&journal_path).unwrap().len(), 5);
+    }
 }
@@ -0,0 +1,14 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask running scans and deletions to stop (e.g. from a Ctrl-C handler).
+/// Repositories already checked are kept; nothing new is started.
+pub fn request() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Whether an interrupt was requested
+pub fn is_requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,280 @@
+use crate::checker::{git_command, now};
+use crate::config;
+use crate::types::{Reason, RepoResult, Status};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// What happened to a delete candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalAction {
+    Deleted,
+    Skipped,
+}
+
+/// How a repository was (or would have been) removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalMethod {
+    Rm,
+    Trash,
+    Quarantine,
+}
+
+impl std::fmt::Display for RemovalMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemovalMethod::Rm => write!(f, "rm"),
+            RemovalMethod::Trash => write!(f, "trash"),
+            RemovalMethod::Quarantine => write!(f, "quarantine"),
+        }
+    }
+}
+
+/// A configured remote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: String,
+}
+
+/// Repository state needed to identify (and re-create) it after deletion
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoSnapshot {
+    /// Remotes with their fetch URLs
+    pub remotes: Vec<RemoteInfo>,
+    /// HEAD commit (None for an unborn HEAD)
+    pub head: Option<String>,
+    /// Current branch (None when detached)
+    pub branch: Option<String>,
+    /// Local branches
+    pub branches: Vec<String>,
+}
+
+impl RepoSnapshot {
+    /// Collect the snapshot; fields that cannot be read are left empty
+    pub fn collect(repo_path: &Path) -> Self {
+        let remotes = git_command(repo_path, &["remote"])
+            .map(|o| {
+                o.lines()
+                    .filter(|l| !l.is_empty())
+                    .map(|name| RemoteInfo {
+                        name: name.to_string(),
+                        url: git_command(repo_path, &["remote", "get-url", name])
+                            .map(|u| u.trim().to_string())
+                            .unwrap_or_default(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let head = git_command(repo_path, &["rev-parse", "--verify", "-q", "HEAD"])
+            .ok()
+            .map(|o| o.trim().to_string());
+        let branch = git_command(repo_path, &["symbolic-ref", "-q", "--short", "HEAD"])
+            .ok()
+            .map(|o| o.trim().to_string());
+        let branches = git_command(repo_path, &["for-each-ref", "--format=%(refname:short)", "refs/heads/"])
+            .map(|o| o.lines().filter(|l| !l.is_empty()).map(String::from).collect())
+            .unwrap_or_default();
+
+        Self {
+            remotes,
+            head,
+            branch,
+            branches,
+        }
+    }
+}
+
+/// One line of the deletion journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub action: JournalAction,
+    #[serde(flatten, with = "crate::paths::path_field")]
+    pub path: PathBuf,
+    pub status: Status,
+    pub reasons: Vec<Reason>,
+    pub method: RemovalMethod,
+    /// Why the repository was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<String>,
+    #[serde(flatten)]
+    pub snapshot: RepoSnapshot,
+}
+
+impl JournalEntry {
+    pub fn new(
+        result: &RepoResult,
+        snapshot: &RepoSnapshot,
+        action: JournalAction,
+        method: RemovalMethod,
+        skip_reason: Option<&str>,
+    ) -> Self {
+        Self {
+            timestamp: now(),
+            action,
+            path: result.path.clone(),
+            status: result.status,
+            reasons: result.reasons.clone(),
+            method,
+            skip_reason: skip_reason.map(String::from),
+            snapshot: snapshot.clone(),
+        }
+    }
+}
+
+/// Default journal file (`$XDG_DATA_HOME/repo-check/journal.jsonl`)
+pub fn default_journal_path() -> PathBuf {
+    config::data_dir().join("journal.jsonl")
+}
+
+/// Append an entry to the journal
+pub fn append(journal_path: &Path, entry: &JournalEntry) -> Result<()> {
+    if let Some(parent) = journal_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(journal_path)
+        .context(format!("Failed to open journal: {}", journal_path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Read all journal entries (oldest first); a missing journal is empty
+pub fn read(journal_path: &Path) -> Result<Vec<JournalEntry>> {
+    if !journal_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(journal_path)
+        .context(format!("Failed to read journal: {}", journal_path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).context(format!(
+                "Invalid journal entry at {}:{}",
+                journal_path.display(),
+                i + 1
+            ))
+        })
+        .collect()
+}
+
+/// Filters for `repo-check history`
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Only entries newer than this
+    pub since: Option<Duration>,
+    pub action: Option<JournalAction>,
+    /// Substring of the repository path
+    pub path: Option<String>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, entry: &JournalEntry, now: u64) -> bool {
+        if let Some(since) = self.since {
+            if entry.timestamp < now.saturating_sub(since.as_secs()) {
+                return false;
+            }
+        }
+        if self.action.is_some_and(|a| a != entry.action) {
+            return false;
+        }
+        if let Some(path) = &self.path {
+            if !entry.path.to_string_lossy().contains(path.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Read journal entries matching the filter
+pub fn query(journal_path: &Path, filter: &HistoryFilter) -> Result<Vec<JournalEntry>> {
+    let now = now();
+    Ok(read(journal_path)?
+        .into_iter()
+        .filter(|e| filter.matches(e, now))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::git;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_collect() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-b", "main"]);
+        git(dir.path(), &["commit", "--allow-empty", "-m", "initial"]);
+        git(dir.path(), &["branch", "feature"]);
+        git(dir.path(), &["remote", "add", "origin", "https://example.com/repo.git"]);
+
+        let snapshot = RepoSnapshot::collect(dir.path());
+        assert_eq!(
+            snapshot.remotes,
+            vec![RemoteInfo {
+                name: "origin".to_string(),
+                url: "https://example.com/repo.git".to_string()
+            }]
+        );
+        assert_eq!(snapshot.head.as_ref().map(|h| h.len()), Some(40));
+        assert_eq!(snapshot.branch.as_deref(), Some("main"));
+        assert_eq!(snapshot.branches, vec!["feature", "main"]);
+    }
+
+    #[test]
+    fn test_append_and_query() {
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join("logs").join("journal.jsonl");
+        let mut result = RepoResult::new(PathBuf::from("/src/app"));
+        result.finalize_safe();
+        let snapshot = RepoSnapshot::default();
+
+        append(
+            &journal_path,
+            &JournalEntry::new(&result, &snapshot, JournalAction::Deleted, RemovalMethod::Rm, None),
+        )
+        .unwrap();
+        result.path = PathBuf::from("/src/lib");
+        let mut skipped =
+            JournalEntry::new(&result, &snapshot, JournalAction::Skipped, RemovalMethod::Trash, Some("declined"));
+        skipped.timestamp -= 10 * 86400;
+        append(&journal_path, &skipped).unwrap();
+
+        assert_eq!(read(&journal_path).unwrap().len(), 2);
+
+        let filter = HistoryFilter {
+            action: Some(JournalAction::Skipped),
+            ..Default::default()
+        };
+        let entries = query(&journal_path, &filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].skip_reason.as_deref(), Some("declined"));
+
+        let filter = HistoryFilter {
+            since: Some(Duration::from_secs(86400)),
+            ..Default::default()
+        };
+        let entries = query(&journal_path, &filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, PathBuf::from("/src/app"));
+
+        let filter = HistoryFilter {
+            path: Some("lib".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query(&journal_path, &filter).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,43 @@
+//! Check whether local git repositories are safe to delete.
+//!
+//! A repository is SAFE when everything in it can be recovered from its
+//! remotes: no uncommitted changes, stashes, local-only commits or tags,
+//! in-progress operations, unsafe worktrees or submodules.
+//!
+//! ```no_run
+//! use repo_check::scanner::Scanner;
+//! use repo_check::types::Status;
+//!
+//! for result in Scanner::new("/home/me/src").depth(2).scan()? {
+//!     if result.status != Status::Safe {
+//!         println!("{}: {:?}", result.path.display(), result.reasons);
+//!     }
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Single repositories can be checked with [`checker::check_repository`],
+//! and [`delete::execute_delete`] re-checks and removes candidates, leaving
+//! confirmations and output to a [`delete::DeleteHandler`].
+//! Custom rules implement [`checks::Check`] and are registered in
+//! [`CheckOptions::checks`].
+
+pub mod backend;
+pub mod bundle;
+pub mod checker;
+pub mod checks;
+pub mod config;
+pub mod delete;
+pub mod interrupt;
+pub mod journal;
+pub mod paths;
+pub mod quarantine;
+pub mod reclone;
+pub mod scanner;
+pub mod types;
+
+#[cfg(test)]
+pub(crate) mod test_util;
+
+pub use scanner::Scanner;
+pub use types::{CheckOptions, Reason, RepoResult, ScanOptions, Status};
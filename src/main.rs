@@ -1,25 +1,241 @@
-mod checker;
 mod cli;
-mod delete;
+mod commands;
 mod output;
-mod scanner;
-mod types;
+mod prompt;
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use cli::Args;
-use std::path::Path;
-use types::Status;
+use colored::Colorize;
+use cli::{Args, Command};
+use repo_check::checks::Checks;
+use repo_check::config::Config;
+use repo_check::types::{CheckOptions, DeleteCandidate, DeleteMethod, DeleteOptions, ScanOptions, Status};
+use repo_check::{checker, delete, interrupt, journal, quarantine, reclone, Scanner};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Options for one target directory: the user config with the target's
+/// `.repo-check.toml` on top, and the command line over both
+struct Target {
+    root: PathBuf,
+    scan_options: ScanOptions,
+    check_options: CheckOptions,
+    method: DeleteMethod,
+}
+
+impl Target {
+    fn new(args: &Args, user: &Config, root: &Path, quarantine_dir: &Path) -> Result<Self> {
+        // An explicit --config replaces the per-directory files
+        let target = match &args.config {
+            Some(_) => None,
+            None => Config::load_target(root)?,
+        };
+        let config = match target {
+            Some(target) => user.clone().merge(target),
+            None => user.clone(),
+        };
+
+        // Patterns from the config files and the command line all apply
+        let mut scan_options = ScanOptions {
+            include_dot: args.include_dot,
+            max_depth: args.max_depth.or(config.max_depth()).unwrap_or(1),
+            include: [config.include.clone(), args.include.clone()].concat(),
+            exclude: [config.exclude.clone(), args.exclude.clone()].concat(),
+            ..Default::default()
+        };
+        if let Some(skipped_dirs) = &config.skipped_dirs {
+            scan_options.skipped_dirs = skipped_dirs.clone();
+        }
+
+        let timeout = args
+            .timeout
+            .or(config.timeout())
+            .unwrap_or(checker::DEFAULT_TIMEOUT);
+        let check_options = CheckOptions {
+            ignore_untracked: args.ignore_untracked().or(config.ignore_untracked).unwrap_or(false),
+            checks: build_checks(args, &config)?,
+            backend: args.backend,
+            timeout: Some(timeout).filter(|t| !t.is_zero()),
+            fetch_jobs: args.fetch_jobs,
+            max_fetch_age: args.max_fetch_age.or(config.max_fetch_age()),
+            probe_remotes: args.probe_remotes,
+            protected_paths: config
+                .protected
+                .iter()
+                .map(|p| p.canonicalize().unwrap_or_else(|_| p.clone()))
+                .collect(),
+        };
+
+        let method = if args.quarantine {
+            DeleteMethod::Quarantine(quarantine_dir.to_path_buf())
+        } else if args.trash().or(config.trash).unwrap_or(false) {
+            DeleteMethod::Trash
+        } else {
+            DeleteMethod::Remove
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            scan_options,
+            check_options,
+            method,
+        })
+    }
+}
+
+/// External-command checks, then config entries, then --check-reflog / --enable / --disable
+fn build_checks(args: &Args, config: &Config) -> Result<Checks> {
+    let mut checks = Checks::builtin();
+    for check in &config.check {
+        checks
+            .try_register(check.clone())
+            .context("Invalid [[check]] entry in config")?;
+    }
+    for (name, enabled) in &config.checks {
+        checks
+            .set_enabled(name, *enabled)
+            .context("Invalid [checks] entry in config")?;
+    }
+    if args.check_reflog {
+        checks.set_enabled("reflog", true)?;
+    }
+    if args.fetch {
+        checks.set_enabled("fetch", true)?;
+    }
+    if args.verify_remote {
+        checks.set_enabled("verify_remote", true)?;
+    }
+    for name in &args.enable {
+        checks.set_enabled(name, true)?;
+    }
+    for name in &args.disable {
+        checks.set_enabled(name, false)?;
+    }
+    Ok(checks)
+}
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    let base_path = Path::new(&args.path)
-        .canonicalize()
-        .context(format!("Failed to resolve path: {}", args.path))?;
+    // An explicit --config replaces the user-wide and per-directory files
+    let config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::load_user()?,
+    };
+    let quarantine_dir = args
+        .quarantine_dir
+        .clone()
+        .or(config.quarantine_dir.clone())
+        .unwrap_or_else(quarantine::default_quarantine_dir);
+    let journal_path = args
+        .journal
+        .clone()
+        .or(config.journal.clone())
+        .unwrap_or_else(journal::default_journal_path);
 
-    // Scan repositories
-    let results = scanner::scan_repositories(&base_path, args.include_dot, args.ignore_untracked);
+    // Quarantine / re-clone / journal subcommands
+    match &args.command {
+        Some(Command::Restore { name, all }) => {
+            let name = if *all { None } else { name.as_deref() };
+            return commands::restore(&quarantine_dir, name);
+        }
+        Some(Command::Purge { older_than, dry_run }) => {
+            return commands::purge(&quarantine_dir, *older_than, *dry_run);
+        }
+        Some(Command::Reclone { manifest, dry_run }) => {
+            return commands::reclone(manifest, *dry_run);
+        }
+        Some(Command::History {
+            since,
+            action,
+            path,
+            json,
+        }) => {
+            let filter = journal::HistoryFilter {
+                since: *since,
+                action: action.map(Into::into),
+                path: path.clone(),
+            };
+            let entries = journal::query(&journal_path, &filter)?;
+            output::print_history(&entries, *json);
+            return Ok(());
+        }
+        Some(Command::Checks) | None => {}
+    }
+
+    // Command-line paths take precedence over configured roots
+    let roots: Vec<PathBuf> = if !args.paths.is_empty() {
+        args.paths.clone()
+    } else if !config.roots.is_empty() {
+        config.roots.clone()
+    } else {
+        vec![PathBuf::from(".")]
+    };
+
+    let targets = roots
+        .iter()
+        .map(|root| Target::new(&args, &config, root, &quarantine_dir))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(Command::Checks) = &args.command {
+        for target in &targets {
+            if targets.len() > 1 {
+                println!("{}:", target.root.display());
+            }
+            output::print_checks(&target.check_options.checks);
+        }
+        return Ok(());
+    }
+    // Reporting with fewer checks is fine, deleting is not
+    if args.delete {
+        for target in &targets {
+            let disabled_core = target.check_options.checks.disabled_core();
+            if !disabled_core.is_empty() {
+                anyhow::bail!(
+                    "Refusing to delete with core checks disabled: {} ({})",
+                    disabled_core.join(", "),
+                    target.root.display()
+                );
+            }
+        }
+    }
+    let delete_options = DeleteOptions {
+        skip_confirm: args.yes,
+        bundle_dir: args.bundle_dir.clone(),
+        journal_path: Some(journal_path),
+        manifest_path: Some(
+            args.manifest
+                .clone()
+                .unwrap_or_else(reclone::default_manifest_path),
+        ),
+    };
+
+    // Ctrl-C stops the scan and still reports what was checked; a second one exits immediately
+    ctrlc::set_handler(|| {
+        if interrupt::is_requested() {
+            std::process::exit(130);
+        }
+        interrupt::request();
+    })
+    .context("Failed to install Ctrl-C handler")?;
+
+    // Scan repositories, remembering which target each one was found in
+    let mut results = Vec::new();
+    let mut found_in = HashMap::new();
+    for (index, target) in targets.iter().enumerate() {
+        if interrupt::is_requested() {
+            break;
+        }
+        let scanner = Scanner::new(&target.root)
+            .scan_options(target.scan_options.clone())
+            .check_options(target.check_options.clone());
+        for result in scanner.scan()? {
+            found_in.entry(result.path.clone()).or_insert(index);
+            results.push(result);
+        }
+    }
+    // Overlapping roots may report the same repository twice (the first target's result is kept)
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+    results.dedup_by(|a, b| a.path == b.path);
 
     // Determine filter
     let filter = if args.only_safe {
@@ -32,22 +248,45 @@ fn main() -> Result<()> {
         None
     };
 
+    // Partial results are printed, but never acted on
+    if interrupt::is_requested() {
+        output::print_filtered(&results, filter, args.json);
+        eprintln!(
+            "{}: Interrupted, only {} repositories were checked",
+            "Warning".yellow(),
+            results.len()
+        );
+        std::process::exit(130);
+    }
+
     // Delete mode
     if args.delete {
-        let candidates = delete::get_delete_candidates(&results, args.allow_unknown);
+        let candidates: Vec<DeleteCandidate> = delete::get_delete_candidates(&results, args.allow_unknown)
+            .into_iter()
+            .map(|result| {
+                let target = &targets[found_in[&result.path]];
+                DeleteCandidate {
+                    result,
+                    check_options: &target.check_options,
+                    method: &target.method,
+                }
+            })
+            .collect();
 
         if candidates.is_empty() {
             println!("No repositories to delete.");
             return Ok(());
         }
 
-        delete::show_delete_candidates(&candidates);
+        output::print_delete_candidates(&candidates.iter().map(|c| c.result).collect::<Vec<_>>());
 
         if args.dry_run {
             println!("\n(dry-run mode: no repositories were deleted)");
         } else {
-            let (deleted, skipped) =
-                delete::execute_delete(&candidates, args.trash, args.yes)?;
+            let (deleted, skipped) = delete::execute_delete(&candidates, &delete_options, &mut prompt::Terminal::default())?;
+            if interrupt::is_requested() {
+                println!("Interrupted.");
+            }
             println!("\nDeleted: {}, Skipped: {}", deleted, skipped);
         }
     } else {
@@ -57,3 +296,46 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_targets_keep_their_own_config() {
+        let base = TempDir::new().unwrap();
+        let scratch = base.path().join("scratch");
+        let work = base.path().join("work");
+        fs::create_dir(&scratch).unwrap();
+        fs::create_dir(&work).unwrap();
+        fs::write(
+            scratch.join(repo_check::config::CONFIG_FILE_NAME),
+            "ignore_untracked = true\nmax_depth = 2\ntrash = true\nskipped_dirs = []\n[checks]\nreflog = true\n",
+        )
+        .unwrap();
+        fs::write(work.join(repo_check::config::CONFIG_FILE_NAME), "timeout = \"5s\"\n").unwrap();
+
+        let args = Args::try_parse_from(["repo-check", "--delete"]).unwrap();
+        let user = Config::default();
+        let quarantine_dir = base.path().join("quarantine");
+        let scratch = Target::new(&args, &user, &scratch, &quarantine_dir).unwrap();
+        let work = Target::new(&args, &user, &work, &quarantine_dir).unwrap();
+
+        assert!(scratch.check_options.ignore_untracked);
+        assert_eq!(scratch.scan_options.max_depth, 2);
+        assert!(scratch.scan_options.skipped_dirs.is_empty());
+        assert_eq!(scratch.method, DeleteMethod::Trash);
+        assert!(scratch.check_options.checks.is_enabled("reflog"));
+        assert_eq!(scratch.check_options.timeout, Some(checker::DEFAULT_TIMEOUT));
+
+        assert!(!work.check_options.ignore_untracked);
+        assert_eq!(work.scan_options.max_depth, 1);
+        assert!(!work.scan_options.skipped_dirs.is_empty());
+        assert_eq!(work.method, DeleteMethod::Remove);
+        assert!(!work.check_options.checks.is_enabled("reflog"));
+        assert_eq!(work.check_options.timeout, Some(Duration::from_secs(5)));
+    }
+}
@@ -1,39 +1,137 @@
-use crate::types::{RepoResult, Status};
-use colored::Colorize;
+use repo_check::checks::Checks;
+use repo_check::journal::{JournalAction, JournalEntry};
+use repo_check::types::{BranchInfo, Reason, RepoResult, Status};
+use colored::{ColoredString, Colorize};
 
-/// Display result for a single repository
-fn print_repo_result(result: &RepoResult) {
-    let path_str = result.path.display().to_string();
-    let status_str = match result.status {
+/// Colored status label
+fn status_label(status: Status) -> ColoredString {
+    match status {
         Status::Safe => "SAFE".green().bold(),
         Status::Unsafe => "UNSAFE".red().bold(),
         Status::Unknown => "UNKNOWN".yellow().bold(),
-    };
-
-    println!("{} [{}]", path_str.bold(), status_str);
+    }
+}
 
+/// Display reasons, counts and errors of a result, prefixed by `indent`
+fn print_details(result: &RepoResult, indent: &str) {
     // Display reasons
     for reason in &result.reasons {
-        println!("  - {}", reason);
+        println!("{}  - {}", indent, reason);
     }
 
     // Auxiliary information
     if result.dirty_count > 0 {
-        println!("    Dirty files: {}", result.dirty_count);
+        println!("{}    Dirty files: {}", indent, result.dirty_count);
     }
     if result.stash_count > 0 {
-        println!("    Stash entries: {}", result.stash_count);
+        println!("{}    Stash entries: {}", indent, result.stash_count);
     }
     if result.local_only_commit_count > 0 {
-        println!("    Local-only commits: {}", result.local_only_commit_count);
+        println!("{}    Local-only commits: {}", indent, result.local_only_commit_count);
+    }
+    if result.local_only_tag_count > 0 {
+        println!("{}    Local-only tags: {}", indent, result.local_only_tag_count);
+    }
+    if result.detached_head_commit_count > 0 {
+        println!("{}    Detached HEAD commits: {}", indent, result.detached_head_commit_count);
+    }
+    if result.reflog_only_commit_count > 0 {
+        println!("{}    Reflog-only commits: {}", indent, result.reflog_only_commit_count);
+    }
+    if result.reasons.contains(&Reason::StaleRemoteRefs) {
+        match result.last_fetch_age {
+            Some(age) => println!("{}    Last fetch: {} ago", indent, format_age(age)),
+            None => println!("{}    Last fetch: unknown", indent),
+        }
+    }
+
+    // Which branches hold the work
+    if result.status != Status::Safe && !result.branches.is_empty() {
+        println!("{}    Branches:", indent);
+        for branch in &result.branches {
+            println!("{}      {}", indent, format_branch(branch));
+        }
     }
 
     // Display errors if any
     for error in &result.errors {
-        println!("    {}: {}", "Error".red(), error);
+        println!("{}    {}: {}", indent, "Error".red(), error);
     }
 }
 
+/// One-line branch summary: `main -> origin/main [ahead 2, 2 local-only] 1a2b3c4 2026-10-01 Subject`
+fn format_branch(branch: &BranchInfo) -> String {
+    let mut line = match &branch.upstream {
+        Some(upstream) => format!("{} -> {}", branch.name, upstream),
+        None => format!("{} (no upstream)", branch.name),
+    };
+    let mut state = Vec::new();
+    if branch.upstream_gone {
+        state.push("gone".to_string());
+    }
+    if branch.ahead > 0 {
+        state.push(format!("ahead {}", branch.ahead));
+    }
+    if branch.behind > 0 {
+        state.push(format!("behind {}", branch.behind));
+    }
+    if branch.local_only_commit_count > 0 {
+        state.push(format!("{} local-only", branch.local_only_commit_count));
+    }
+    if !state.is_empty() {
+        line.push_str(&format!(" [{}]", state.join(", ")));
+    }
+    let date = format_timestamp(branch.date);
+    format!(
+        "{} {} {} {}",
+        line,
+        &branch.tip[..branch.tip.len().min(7)],
+        &date[..10],
+        branch.subject
+    )
+}
+
+/// Display linked worktrees and submodules (recursively) under their parent
+fn print_children(result: &RepoResult, indent: &str) {
+    let child_indent = format!("{}  ", indent);
+    for worktree in &result.worktrees {
+        println!(
+            "{}Worktree: {} [{}]",
+            child_indent,
+            worktree.path.display(),
+            status_label(worktree.status)
+        );
+        print_details(worktree, &child_indent);
+    }
+    for submodule in &result.submodules {
+        println!(
+            "{}Submodule: {} [{}]",
+            child_indent,
+            submodule.path.display(),
+            status_label(submodule.status)
+        );
+        print_details(submodule, &child_indent);
+        print_children(submodule, &child_indent);
+    }
+}
+
+/// Display result for a single repository
+fn print_repo_result(result: &RepoResult) {
+    let path_str = result.path.display().to_string();
+    match result.protected {
+        Some(protection) => println!(
+            "{} [{}] {} ({})",
+            path_str.bold(),
+            status_label(result.status),
+            "PROTECTED".cyan().bold(),
+            protection
+        ),
+        None => println!("{} [{}]", path_str.bold(), status_label(result.status)),
+    }
+    print_details(result, "");
+    print_children(result, "");
+}
+
 /// Display summary
 fn print_summary(results: &[RepoResult]) {
     let safe_count = results.iter().filter(|r| r.status == Status::Safe).count();
@@ -84,3 +182,154 @@ pub fn print_filtered(results: &[RepoResult], filter: Option<Status>, json: bool
         print_summary(results);
     }
 }
+
+/// Display deletion candidates
+pub fn print_delete_candidates(candidates: &[&RepoResult]) {
+    println!("The following repositories will be deleted:\n");
+    for result in candidates {
+        let status_str = match result.status {
+            Status::Safe => "SAFE".green(),
+            Status::Unknown => "UNKNOWN".yellow(),
+            _ => "?".normal(),
+        };
+        println!("  {} [{}]", result.path.display(), status_str);
+    }
+    println!("\nTotal: {} repositories", candidates.len());
+}
+
+/// Print registered checks and whether they are enabled
+pub fn print_checks(checks: &Checks) {
+    for (check, enabled) in checks.iter() {
+        let state = if enabled { "enabled".green() } else { "disabled".dimmed() };
+        println!("{:<16} {:<8}  {}", check.name(), state, check.description());
+    }
+}
+
+/// Format an age in seconds in its largest unit (`20d`, `5h`, `3m`, `10s`)
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        s if s >= 86400 => format!("{}d", s / 86400),
+        s if s >= 3600 => format!("{}h", s / 3600),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Display journal entries
+pub fn print_history(entries: &[JournalEntry], json: bool) {
+    if json {
+        let json_str = serde_json::to_string_pretty(entries).unwrap_or_else(|_| "[]".to_string());
+        println!("{}", json_str);
+        return;
+    }
+
+    if entries.is_empty() {
+        println!("No journal entries found.");
+        return;
+    }
+
+    for entry in entries {
+        let action = match entry.action {
+            JournalAction::Deleted => "DELETED".red().bold(),
+            JournalAction::Skipped => "SKIPPED".yellow().bold(),
+        };
+        println!(
+            "{} {} {} [{}] ({})",
+            format_timestamp(entry.timestamp),
+            action,
+            entry.path.display().to_string().bold(),
+            entry.status,
+            entry.method
+        );
+        if let Some(skip_reason) = &entry.skip_reason {
+            println!("  Skipped: {}", skip_reason);
+        }
+        for reason in &entry.reasons {
+            println!("  - {}", reason);
+        }
+        for remote in &entry.snapshot.remotes {
+            println!("    Remote {}: {}", remote.name, remote.url);
+        }
+        if let Some(head) = &entry.snapshot.head {
+            match &entry.snapshot.branch {
+                Some(branch) => println!("    HEAD: {} ({})", head, branch),
+                None => println!("    HEAD: {} (detached)", head),
+            }
+        }
+        if !entry.snapshot.branches.is_empty() {
+            println!("    Branches: {}", entry.snapshot.branches.join(", "));
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951782400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1792152000), "2026-10-16 12:00:00 UTC");
+    }
+
+    #[test]
+    fn test_format_branch() {
+        let mut branch = BranchInfo {
+            name: "main".to_string(),
+            upstream: Some("origin/main".to_string()),
+            upstream_gone: false,
+            ahead: 2,
+            behind: 1,
+            local_only_commit_count: 2,
+            tip: "1a2b3c4d5e6f".to_string(),
+            subject: "Fix parser".to_string(),
+            date: 1792152000,
+        };
+        assert_eq!(
+            format_branch(&branch),
+            "main -> origin/main [ahead 2, behind 1, 2 local-only] 1a2b3c4 2026-10-16 Fix parser"
+        );
+
+        branch.upstream = None;
+        branch.ahead = 0;
+        branch.behind = 0;
+        assert_eq!(format_branch(&branch), "main (no upstream) [2 local-only] 1a2b3c4 2026-10-16 Fix parser");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(10), "10s");
+        assert_eq!(format_age(3 * 60 + 5), "3m");
+        assert_eq!(format_age(5 * 3600), "5h");
+        assert_eq!(format_age(20 * 86400 + 3600), "20d");
+    }
+}
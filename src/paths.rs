@@ -0,0 +1,101 @@
+//! Lossless handling of paths that are not valid UTF-8.
+//!
+//! Paths are passed to git as `OsStr` and read back from git output as raw
+//! bytes. In JSON a path is written as a (lossy) string, and a non-UTF-8 path
+//! additionally as its raw bytes (`path_bytes`), which take precedence when it
+//! is read back.
+
+use std::path::{Path, PathBuf};
+
+/// Path from raw bytes as printed by git
+#[cfg(unix)]
+pub fn from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+/// Path from raw bytes as printed by git
+#[cfg(not(unix))]
+pub fn from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Raw bytes of a path that is not valid UTF-8 (None for UTF-8 paths)
+#[cfg(unix)]
+pub fn non_utf8_bytes(path: &Path) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+    path.to_str().is_none().then(|| path.as_os_str().as_bytes().to_vec())
+}
+
+/// Raw bytes of a path that is not valid UTF-8 (None for UTF-8 paths)
+#[cfg(not(unix))]
+pub fn non_utf8_bytes(_path: &Path) -> Option<Vec<u8>> {
+    None
+}
+
+/// Serde `with` module for a flattened path field written as `$key` plus `$bytes_key`
+macro_rules! lossless_path_field {
+    ($module:ident, $key:literal, $bytes_key:literal) => {
+        #[doc = concat!("`#[serde(flatten, with = \"...\")]` for a path written as `", $key, "` and `", $bytes_key, "`")]
+        pub mod $module {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+            use std::path::{Path, PathBuf};
+
+            #[derive(Serialize, Deserialize)]
+            struct Repr {
+                #[serde(rename = $key)]
+                path: String,
+                #[serde(rename = $bytes_key, default, skip_serializing_if = "Option::is_none")]
+                bytes: Option<Vec<u8>>,
+            }
+
+            pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+                Repr {
+                    path: path.to_string_lossy().into_owned(),
+                    bytes: super::non_utf8_bytes(path),
+                }
+                .serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+                let repr = Repr::deserialize(deserializer)?;
+                Ok(match repr.bytes {
+                    Some(bytes) => super::from_bytes(&bytes),
+                    None => PathBuf::from(repr.path),
+                })
+            }
+        }
+    };
+}
+
+lossless_path_field!(path_field, "path", "path_bytes");
+lossless_path_field!(original_path_field, "original_path", "original_path_bytes");
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        #[serde(flatten, with = "path_field")]
+        path: PathBuf,
+        status: String,
+    }
+
+    #[test]
+    fn test_non_utf8_path_round_trip() {
+        let utf8 = Entry { path: PathBuf::from("/src/repo"), status: "SAFE".into() };
+        let json = serde_json::to_string(&utf8).unwrap();
+        assert_eq!(json, r#"{"path":"/src/repo","status":"SAFE"}"#);
+        assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), utf8);
+
+        let latin1 = Entry { path: from_bytes(b"/src/caf\xe9"), status: "SAFE".into() };
+        let json = serde_json::to_string(&latin1).unwrap();
+        assert_eq!(
+            json,
+            r#"{"path":"/src/caf�","path_bytes":[47,115,114,99,47,99,97,102,233],"status":"SAFE"}"#
+        );
+        assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), latin1);
+    }
+}
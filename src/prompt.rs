@@ -0,0 +1,72 @@
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Select};
+use repo_check::delete::{DeleteEvent, DeleteHandler};
+use repo_check::types::{DeleteConfirm, DeleteMethod};
+use std::path::Path;
+
+/// Asks on the terminal and prints the progress of delete mode
+#[derive(Default)]
+pub struct Terminal {
+    /// "Deleting ..." was printed and waits for the outcome on the same line
+    removing: bool,
+}
+
+impl DeleteHandler for Terminal {
+    fn confirm(&mut self, path: &Path) -> DeleteConfirm {
+        let path_str = path.display().to_string();
+        println!("\nDelete {}?", path_str.bold());
+
+        let options = &["Yes", "No", "All (delete all remaining)", "Quit"];
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .items(options)
+            .default(1) // Default is "No"
+            .interact_opt();
+
+        match selection {
+            Ok(Some(0)) => DeleteConfirm::Yes,
+            Ok(Some(1)) => DeleteConfirm::No,
+            Ok(Some(2)) => DeleteConfirm::All,
+            Ok(Some(3)) => {
+                println!("Aborted.");
+                DeleteConfirm::Quit
+            }
+            _ => DeleteConfirm::No,
+        }
+    }
+
+    fn confirm_permanent(&mut self, _path: &Path, error: &dyn std::error::Error) -> bool {
+        eprintln!("{}: Failed to move to trash: {}", "Warning".yellow(), error);
+        let options = &["Yes, use rm -rf instead", "No, skip this repository"];
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Fall back to permanent deletion?")
+            .items(options)
+            .default(1)
+            .interact_opt();
+        matches!(selection, Ok(Some(0)))
+    }
+
+    fn report(&mut self, event: DeleteEvent) {
+        let removing = std::mem::take(&mut self.removing);
+        match event {
+            DeleteEvent::Bundled { path, bundle } => {
+                println!("Bundled {} -> {}", path.display(), bundle.display());
+            }
+            DeleteEvent::Removing { path, method } => {
+                match method {
+                    DeleteMethod::Quarantine(_) => print!("Quarantining {}... ", path.display()),
+                    _ => print!("Deleting {}... ", path.display()),
+                }
+                self.removing = true;
+            }
+            DeleteEvent::Removed { .. } => println!("{}", "done".green()),
+            DeleteEvent::Skipped { reason, .. } if removing => println!("{}: {}", "skipped".yellow(), reason),
+            DeleteEvent::Skipped { path, reason } => {
+                println!("{} {}: {}", "Skipped".yellow(), path.display(), reason);
+            }
+            DeleteEvent::ManifestWritten(path) => {
+                println!("Re-clone manifest written to {}", path.display());
+            }
+            DeleteEvent::Warning(message) => eprintln!("{}: {}", "Warning".yellow(), message),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use crate::checker::now;
+use crate::config;
+use crate::types::RepoResult;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Manifest file name inside each quarantine entry
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directory (inside each quarantine entry) holding the moved repository
+const REPO_DIR: &str = "repo";
+
+/// Record of a quarantined repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineManifest {
+    /// Where the repository was moved from
+    #[serde(flatten, with = "crate::paths::original_path_field")]
+    pub original_path: PathBuf,
+    /// When the repository was quarantined (seconds since the Unix epoch)
+    pub quarantined_at: u64,
+    /// Check result at the time of quarantine
+    pub result: RepoResult,
+}
+
+/// A quarantine entry on disk
+#[derive(Debug, Clone)]
+pub struct QuarantineEntry {
+    /// Entry directory (`<quarantine dir>/<id>`)
+    pub dir: PathBuf,
+    pub manifest: QuarantineManifest,
+}
+
+impl QuarantineEntry {
+    /// Entry identifier (directory name)
+    pub fn id(&self) -> String {
+        self.dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Whether `name` refers to this entry (entry id or original directory name)
+    fn matches(&self, name: &str) -> bool {
+        self.id() == name
+            || self
+                .manifest
+                .original_path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy() == name)
+    }
+}
+
+/// A repository moved into quarantine
+#[derive(Debug)]
+pub struct Quarantined {
+    /// Entry directory
+    pub dir: PathBuf,
+    /// Why the original could not be removed completely after it was copied
+    /// across filesystems (the entry holds the complete copy)
+    pub leftover: Option<io::Error>,
+}
+
+/// Default quarantine directory (`$XDG_DATA_HOME/repo-check/quarantine`)
+pub fn default_quarantine_dir() -> PathBuf {
+    config::data_dir().join("quarantine")
+}
+
+/// Recursively copy a directory (symlinks are copied as links)
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+            #[cfg(not(unix))]
+            fs::copy(entry.path(), &target).map(|_| ())?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Move a directory, falling back to copy + remove across filesystems.
+/// Once the copy is complete the move has succeeded: a failure to remove the
+/// source is returned as `Ok(Some(error))`, with the source (partly) left in place.
+fn move_dir(from: &Path, to: &Path) -> io::Result<Option<io::Error>> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_dir(from, to) {
+                let _ = fs::remove_dir_all(to);
+                return Err(e);
+            }
+            Ok(fs::remove_dir_all(from).err())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Move a repository into the quarantine directory and write its manifest
+pub fn quarantine_repository(result: &RepoResult, quarantine_dir: &Path) -> Result<Quarantined> {
+    fs::create_dir_all(quarantine_dir).context(format!(
+        "Failed to create quarantine directory: {}",
+        quarantine_dir.display()
+    ))?;
+
+    let name = result
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+    let quarantined_at = now();
+
+    // Reserve a unique entry directory
+    let mut entry_dir = quarantine_dir.join(format!("{}-{}", name, quarantined_at));
+    let mut suffix = 1;
+    while entry_dir.exists() {
+        entry_dir = quarantine_dir.join(format!("{}-{}-{}", name, quarantined_at, suffix));
+        suffix += 1;
+    }
+    fs::create_dir(&entry_dir)?;
+
+    let manifest = QuarantineManifest {
+        original_path: result.path.clone(),
+        quarantined_at,
+        result: result.clone(),
+    };
+    let written = serde_json::to_string_pretty(&manifest)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(fs::write(entry_dir.join(MANIFEST_FILE), json)?))
+        .and_then(|_| Ok(move_dir(&result.path, &entry_dir.join(REPO_DIR))?));
+
+    match written {
+        Ok(leftover) => Ok(Quarantined {
+            dir: entry_dir,
+            leftover,
+        }),
+        Err(e) => {
+            let _ = fs::remove_dir_all(&entry_dir);
+            Err(e)
+        }
+    }
+}
+
+/// List quarantine entries (oldest first)
+pub fn list_entries(quarantine_dir: &Path) -> Result<Vec<QuarantineEntry>> {
+    if !quarantine_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(quarantine_dir)?.flatten() {
+        let dir = entry.path();
+        let manifest_path = dir.join(MANIFEST_FILE);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let content = fs::read_to_string(&manifest_path)?;
+        let manifest: QuarantineManifest = serde_json::from_str(&content)
+            .context(format!("Invalid manifest: {}", manifest_path.display()))?;
+        entries.push(QuarantineEntry { dir, manifest });
+    }
+
+    entries.sort_by(|a, b| {
+        (a.manifest.quarantined_at, a.id()).cmp(&(b.manifest.quarantined_at, b.id()))
+    });
+    Ok(entries)
+}
+
+/// Move a quarantined repository back to its original path
+pub fn restore_entry(entry: &QuarantineEntry) -> Result<()> {
+    let target = &entry.manifest.original_path;
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Leftovers of an incomplete removal go with the entry directory
+    let _leftover = move_dir(&entry.dir.join(REPO_DIR), target)?;
+    fs::remove_dir_all(&entry.dir)?;
+    Ok(())
+}
+
+/// Entries to restore: the one matching `name` (entry id or original
+/// directory name), or every entry when `name` is None
+pub fn select_entries(quarantine_dir: &Path, name: Option<&str>) -> Result<Vec<QuarantineEntry>> {
+    let entries = list_entries(quarantine_dir)?;
+    let Some(name) = name else {
+        return Ok(entries);
+    };
+    let matched: Vec<QuarantineEntry> = entries.into_iter().filter(|e| e.matches(name)).collect();
+    if matched.is_empty() {
+        anyhow::bail!("No quarantined repository named {}", name);
+    }
+    if matched.len() > 1 {
+        let ids: Vec<String> = matched.iter().map(|e| e.id()).collect();
+        anyhow::bail!(
+            "Multiple quarantined repositories named {}, specify one of: {}",
+            name,
+            ids.join(", ")
+        );
+    }
+    Ok(matched)
+}
+
+/// Entries quarantined more than `older_than` ago
+pub fn expired_entries(quarantine_dir: &Path, older_than: Duration) -> Result<Vec<QuarantineEntry>> {
+    let cutoff = now().saturating_sub(older_than.as_secs());
+    Ok(list_entries(quarantine_dir)?
+        .into_iter()
+        .filter(|entry| entry.manifest.quarantined_at <= cutoff)
+        .collect())
+}
+
+/// Permanently remove a quarantine entry
+pub fn purge_entry(entry: &QuarantineEntry) -> Result<()> {
+    fs::remove_dir_all(&entry.dir).context(format!("Failed to remove {}", entry.dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn make_repo(base: &Path, name: &str) -> RepoResult {
+        let path = base.join(name);
+        fs::create_dir_all(path.join(".git")).unwrap();
+        fs::write(path.join("file.txt"), name).unwrap();
+        RepoResult::new(path)
+    }
+
+    #[test]
+    fn test_quarantine_and_restore() {
+        let base = TempDir::new().unwrap();
+        let quarantine_dir = base.path().join("quarantine");
+        let result = make_repo(base.path(), "app");
+
+        let quarantined = quarantine_repository(&result, &quarantine_dir).unwrap();
+        assert!(quarantined.leftover.is_none());
+        assert!(!result.path.exists());
+        assert!(quarantined.dir.join(REPO_DIR).join("file.txt").is_file());
+
+        let entries = list_entries(&quarantine_dir).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].manifest.original_path, result.path);
+
+        assert!(select_entries(&quarantine_dir, Some("missing")).is_err());
+        let selected = select_entries(&quarantine_dir, Some("app")).unwrap();
+        assert_eq!(selected.len(), 1);
+        restore_entry(&selected[0]).unwrap();
+        assert_eq!(fs::read_to_string(result.path.join("file.txt")).unwrap(), "app");
+        assert!(list_entries(&quarantine_dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_all_skips_existing_target() {
+        let base = TempDir::new().unwrap();
+        let quarantine_dir = base.path().join("quarantine");
+        let first = make_repo(base.path(), "first");
+        let second = make_repo(base.path(), "second");
+        quarantine_repository(&first, &quarantine_dir).unwrap();
+        quarantine_repository(&second, &quarantine_dir).unwrap();
+
+        // Something new was created at the original path in the meantime
+        fs::create_dir(&second.path).unwrap();
+
+        let selected = select_entries(&quarantine_dir, None).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert!(restore_entry(&selected[0]).is_ok());
+        assert!(restore_entry(&selected[1]).is_err());
+        assert!(first.path.join("file.txt").is_file());
+        assert_eq!(list_entries(&quarantine_dir).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_purge_older_than() {
+        let base = TempDir::new().unwrap();
+        let quarantine_dir = base.path().join("quarantine");
+        let result = make_repo(base.path(), "app");
+        let entry_dir = quarantine_repository(&result, &quarantine_dir).unwrap().dir;
+
+        // Recent entries are kept
+        assert!(expired_entries(&quarantine_dir, Duration::from_secs(3600)).unwrap().is_empty());
+
+        // Backdate the entry by two days
+        let manifest_path = entry_dir.join(MANIFEST_FILE);
+        let mut manifest: QuarantineManifest =
+            serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        manifest.quarantined_at -= 2 * 86400;
+        fs::write(&manifest_path, serde_json::to_string(&manifest).unwrap()).unwrap();
+
+        let expired = expired_entries(&quarantine_dir, Duration::from_secs(86400)).unwrap();
+        assert_eq!(expired.len(), 1);
+        purge_entry(&expired[0]).unwrap();
+        assert!(!entry_dir.exists());
+    }
+}
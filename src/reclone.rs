@@ -0,0 +1,227 @@
+use crate::checker::{self, git_command, now};
+use crate::config;
+use crate::journal::{RemoteInfo, RepoSnapshot};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A deleted repository that can be cloned back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Original repository path
+    #[serde(flatten, with = "crate::paths::path_field")]
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub snapshot: RepoSnapshot,
+}
+
+impl ManifestEntry {
+    /// Remote to clone from: origin, otherwise the first recorded remote
+    pub fn primary_remote(&self) -> Option<&RemoteInfo> {
+        let remotes = &self.snapshot.remotes;
+        remotes.iter().find(|r| r.name == "origin").or_else(|| remotes.first())
+    }
+
+    /// URL of `remote` as recorded, except that relative paths (which git
+    /// resolves against the working tree) are resolved against the repository
+    /// path. This is done lexically, since the working tree is gone.
+    pub fn remote_url(&self, remote: &RemoteInfo) -> OsString {
+        match checker::local_remote_path(&remote.url, Path::new("")) {
+            Some(path) if path.is_relative() => {
+                let mut resolved = self.path.clone();
+                for component in path.components() {
+                    match component {
+                        Component::ParentDir => {
+                            resolved.pop();
+                        }
+                        Component::CurDir => {}
+                        component => resolved.push(component),
+                    }
+                }
+                resolved.into_os_string()
+            }
+            _ => OsString::from(&remote.url),
+        }
+    }
+}
+
+/// Re-clone manifest written by delete mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecloneManifest {
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub repositories: Vec<ManifestEntry>,
+}
+
+/// Default manifest path (`$XDG_DATA_HOME/repo-check/manifests/reclone-<timestamp>.json`)
+pub fn default_manifest_path() -> PathBuf {
+    config::data_dir()
+        .join("manifests")
+        .join(format!("reclone-{}.json", now()))
+}
+
+/// Write a manifest of deleted repositories
+pub fn write_manifest(manifest_path: &Path, repositories: Vec<ManifestEntry>) -> Result<()> {
+    if let Some(parent) = manifest_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let manifest = RecloneManifest {
+        created_at: now(),
+        repositories,
+    };
+    fs::write(manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .context(format!("Failed to write manifest: {}", manifest_path.display()))?;
+    Ok(())
+}
+
+/// Read a manifest
+pub fn read_manifest(manifest_path: &Path) -> Result<RecloneManifest> {
+    let content = fs::read_to_string(manifest_path)
+        .context(format!("Failed to read manifest: {}", manifest_path.display()))?;
+    serde_json::from_str(&content).context(format!("Invalid manifest: {}", manifest_path.display()))
+}
+
+/// Clone a repository back to its original path and check out the recorded
+/// branch (or the recorded HEAD when it was detached or the branch is gone).
+/// Returns the checked out commit if it differs from the recorded HEAD.
+pub fn reclone_entry(entry: &ManifestEntry) -> Result<Option<String>> {
+    let path = &entry.path;
+    if path.exists() {
+        anyhow::bail!("{} already exists", path.display());
+    }
+    let primary = entry
+        .primary_remote()
+        .ok_or_else(|| anyhow::anyhow!("No remotes recorded"))?;
+
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid path: {}", path.display()))?;
+    fs::create_dir_all(parent)?;
+    git_command(
+        parent,
+        &[
+            OsStr::new("clone"),
+            OsStr::new("--origin"),
+            OsStr::new(&primary.name),
+            OsStr::new("--"),
+            &entry.remote_url(primary),
+            path.as_os_str(),
+        ],
+    )?;
+
+    for remote in entry.snapshot.remotes.iter().filter(|r| r.name != primary.name) {
+        git_command(
+            path,
+            &[
+                OsStr::new("remote"),
+                OsStr::new("add"),
+                OsStr::new("--"),
+                OsStr::new(&remote.name),
+                &entry.remote_url(remote),
+            ],
+        )?;
+    }
+
+    let head = entry.snapshot.head.as_deref();
+    let checked_out = match &entry.snapshot.branch {
+        Some(branch) => git_command(path, &["checkout", branch]).is_ok(),
+        None => false,
+    };
+    if !checked_out {
+        if let Some(head) = head {
+            git_command(path, &["checkout", "--detach", head])
+                .context("Recorded branch and HEAD are not available from the remote")?;
+        }
+    }
+
+    // The remote may have moved on since deletion
+    if let Some(head) = head {
+        if let Ok(current) = git_command(path, &["rev-parse", "HEAD"]) {
+            if current.trim() != head {
+                return Ok(Some(current.trim().to_string()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{git, setup_cloned_repo};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reclone_from_bare_remote() {
+        let (base, remote, repo) = setup_cloned_repo(&[]);
+        let mirror = base.path().join("mirror.git");
+        git(base.path(), &["init", "--bare", mirror.to_str().unwrap()]);
+        git(&repo, &["checkout", "-b", "feature"]);
+        git(&repo, &["commit", "--allow-empty", "-m", "feature"]);
+        git(&repo, &["remote", "add", "mirror", mirror.to_str().unwrap()]);
+        git(&repo, &["push", "--all", "origin"]);
+        // Relative to the working tree, not to wherever reclone runs
+        git(&repo, &["remote", "set-url", "origin", "../remote.git"]);
+
+        // Record and delete the repository, as delete mode does
+        let snapshot = RepoSnapshot::collect(&repo);
+        let head = snapshot.head.clone().unwrap();
+        let manifest_path = base.path().join("manifest.json");
+        write_manifest(
+            &manifest_path,
+            vec![ManifestEntry {
+                path: repo.clone(),
+                snapshot,
+            }],
+        )
+        .unwrap();
+        fs::remove_dir_all(&repo).unwrap();
+
+        let manifest = read_manifest(&manifest_path).unwrap();
+        assert_eq!(manifest.repositories.len(), 1);
+        let entry = &manifest.repositories[0];
+        assert_eq!(reclone_entry(entry).unwrap(), None);
+        assert_eq!(git(&repo, &["symbolic-ref", "--short", "HEAD"]), "feature");
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), head);
+        assert_eq!(git(&repo, &["remote"]), "mirror\norigin");
+        assert_eq!(git(&repo, &["remote", "get-url", "origin"]), remote.to_str().unwrap());
+
+        // Existing paths are never overwritten
+        assert!(reclone_entry(entry).is_err());
+    }
+
+    #[test]
+    fn test_primary_remote() {
+        let remote = |name: &str, url: &str| RemoteInfo {
+            name: name.to_string(),
+            url: url.to_string(),
+        };
+        let mut entry = ManifestEntry {
+            path: PathBuf::from("/src/app"),
+            snapshot: RepoSnapshot::default(),
+        };
+        assert!(entry.primary_remote().is_none());
+        entry.snapshot.remotes = vec![remote("mirror", "git@host:app.git"), remote("origin", "../app.git")];
+        let primary = entry.primary_remote().unwrap();
+        assert_eq!(primary.name, "origin");
+        assert_eq!(entry.remote_url(primary), OsString::from("/src/app.git"));
+        assert_eq!(entry.remote_url(&entry.snapshot.remotes[0]), OsString::from("git@host:app.git"));
+        for url in ["/srv/app.git", "file:///srv/app.git", "https://host/app.git"] {
+            assert_eq!(entry.remote_url(&remote("origin", url)), OsString::from(url));
+        }
+    }
+
+    #[test]
+    fn test_reclone_without_remote_fails() {
+        let base = TempDir::new().unwrap();
+        let entry = ManifestEntry {
+            path: base.path().join("app"),
+            snapshot: RepoSnapshot::default(),
+        };
+
+        assert!(reclone_entry(&entry).is_err());
+        assert!(!entry.path.exists());
+    }
+}
@@ -1,78 +1,285 @@
 use crate::checker::check_repository;
-use crate::types::RepoResult;
+use crate::interrupt;
+use crate::types::{CheckOptions, RepoResult, ScanOptions};
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use rayon::prelude::*;
+use std::ffi::OsStr;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Resolve a gitfile (`.git` file containing `gitdir: <path>`) to its git directory.
+/// Used by linked worktrees, submodules and `--separate-git-dir` repositories.
+fn read_gitfile(git_path: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(git_path).ok()?;
+    let target = content.lines().next()?.strip_prefix("gitdir:")?.trim();
+    let target = Path::new(target);
+    let resolved = if target.is_absolute() {
+        target.to_path_buf()
+    } else {
+        git_path.parent()?.join(target)
+    };
+    resolved.is_dir().then_some(resolved)
+}
 
 /// Check if a directory is a Git repository.
-/// Only targets normal repositories where .git is a directory (excludes submodules).
+/// `.git` may be a directory or a gitfile pointing at the actual git directory.
 fn is_git_repository(path: &Path) -> bool {
     let git_path = path.join(".git");
-    git_path.exists() && git_path.is_dir()
+    if git_path.is_dir() {
+        return true;
+    }
+    git_path.is_file() && read_gitfile(&git_path).is_some()
+}
+
+/// Directory names not descended into by default when scanning recursively
+/// (`ScanOptions::skipped_dirs`)
+pub const DEFAULT_SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".cache",
+];
+
+/// Compile glob patterns (`*` does not cross `/`, `**` does)
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .context(format!("Invalid glob pattern: {}", pattern))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+/// Include/exclude filter applied to paths relative to the base path, and
+/// directory names that are not descended into
+struct PathFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+    skipped_dirs: Vec<String>,
+}
+
+impl PathFilter {
+    fn new(options: &ScanOptions) -> Result<Self> {
+        Ok(Self {
+            include: build_globset(&options.include)?,
+            exclude: build_globset(&options.exclude)?,
+            skipped_dirs: options.skipped_dirs.clone(),
+        })
+    }
+
+    fn is_skipped(&self, name: &OsStr) -> bool {
+        name.to_str()
+            .is_some_and(|name| self.skipped_dirs.iter().any(|skipped| skipped == name))
+    }
+
+    /// Excluded paths are neither reported nor descended into
+    fn is_excluded(&self, relative: &Path) -> bool {
+        self.exclude.is_match(relative)
+    }
+
+    /// Repositories must match an include pattern (if any are given)
+    fn is_included(&self, relative: &Path) -> bool {
+        self.include.is_empty() || self.include.is_match(relative)
+    }
 }
 
-/// Find Git repositories directly under the base path
-pub fn find_repositories(base_path: &Path, include_dot: bool) -> Vec<std::path::PathBuf> {
+/// Find Git repositories under the base path (up to `max_depth` levels deep)
+pub fn find_repositories(base_path: &Path, options: &ScanOptions) -> Result<Vec<PathBuf>> {
+    let filter = PathFilter::new(options)?;
     let mut repos = Vec::new();
 
     // Check the base directory itself (when --include-dot)
-    if include_dot {
+    if options.include_dot {
+        let relative = Path::new(".");
         if base_path.symlink_metadata().map(|m| m.is_symlink()).unwrap_or(false) {
             eprintln!("Warning: Skipping symlink: {}", base_path.display());
-        } else if is_git_repository(base_path) {
+        } else if is_git_repository(base_path)
+            && !filter.is_excluded(relative)
+            && filter.is_included(relative)
+        {
             repos.push(base_path.to_path_buf());
         }
     }
 
-    // Scan immediate subdirectories (depth=1)
-    match fs::read_dir(base_path) {
-        Ok(entries) => {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                // Skip symlinks
-                if path.symlink_metadata().map(|m| m.is_symlink()).unwrap_or(false) {
-                    eprintln!("Warning: Skipping symlink: {}", path.display());
-                    continue;
-                }
-                if path.is_dir() && is_git_repository(&path) {
-                    repos.push(path);
-                }
+    walk_directory(base_path, base_path, 1, options.max_depth, &filter, &mut repos);
+
+    // Sort alphabetically
+    repos.sort();
+    Ok(repos)
+}
+
+/// Collect repositories in `dir` (at `depth`), descending into non-repository
+/// subdirectories until `max_depth` is reached
+fn walk_directory(
+    base_path: &Path,
+    dir: &Path,
+    depth: usize,
+    max_depth: usize,
+    filter: &PathFilter,
+    repos: &mut Vec<PathBuf>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Warning: Failed to read directory {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Skip symlinks
+        if path.symlink_metadata().map(|m| m.is_symlink()).unwrap_or(false) {
+            eprintln!("Warning: Skipping symlink: {}", path.display());
+            continue;
+        }
+        if !path.is_dir() {
+            continue;
+        }
+        let relative = path.strip_prefix(base_path).unwrap_or(&path);
+        if filter.is_excluded(relative) {
+            continue;
+        }
+        // Stop descending once a repository is found
+        if is_git_repository(&path) {
+            if filter.is_included(relative) {
+                repos.push(path);
             }
+            continue;
         }
-        Err(e) => {
-            eprintln!("Warning: Failed to read directory {}: {}", base_path.display(), e);
+        if depth < max_depth && !filter.is_skipped(&entry.file_name()) {
+            walk_directory(base_path, &path, depth + 1, max_depth, filter, repos);
         }
     }
-
-    // Sort alphabetically
-    repos.sort();
-    repos
 }
 
 /// Scan all repositories (parallel execution, results in alphabetical order)
 pub fn scan_repositories(
     base_path: &Path,
-    include_dot: bool,
-    ignore_untracked: bool,
-) -> Vec<RepoResult> {
-    let repos = find_repositories(base_path, include_dot);
+    scan_options: &ScanOptions,
+    options: &CheckOptions,
+) -> Result<Vec<RepoResult>> {
+    let repos = find_repositories(base_path, scan_options)?;
 
-    // Execute checks in parallel
+    // Execute checks in parallel. After an interrupt nothing new is started and
+    // checks still running are dropped (their git commands were interrupted too).
     let mut results: Vec<RepoResult> = repos
         .par_iter()
-        .map(|repo_path| check_repository(repo_path, ignore_untracked))
+        .filter_map(|repo_path| {
+            if interrupt::is_requested() {
+                return None;
+            }
+            let result = check_repository(repo_path, options);
+            (!interrupt::is_requested()).then_some(result)
+        })
         .collect();
 
     // Sort alphabetically (parallel execution makes order non-deterministic)
     results.sort_by(|a, b| a.path.cmp(&b.path));
 
-    results
+    Ok(results)
+}
+
+/// Builder for scanning a directory tree
+///
+/// ```no_run
+/// use repo_check::scanner::Scanner;
+///
+/// let results = Scanner::new("/home/me/src").depth(2).ignore_untracked(true).scan()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    root: PathBuf,
+    scan_options: ScanOptions,
+    check_options: CheckOptions,
+}
+
+impl Scanner {
+    /// Scan `root` with the default options
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            scan_options: ScanOptions::default(),
+            check_options: CheckOptions::default(),
+        }
+    }
+
+    /// Maximum directory depth to search (1 = immediate subdirectories only)
+    pub fn depth(mut self, max_depth: usize) -> Self {
+        self.scan_options.max_depth = max_depth;
+        self
+    }
+
+    /// Include the root directory itself as a target
+    pub fn include_dot(mut self, include_dot: bool) -> Self {
+        self.scan_options.include_dot = include_dot;
+        self
+    }
+
+    /// Only scan repositories matching this glob pattern
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.scan_options.include.push(pattern.into());
+        self
+    }
+
+    /// Never scan paths matching this glob pattern
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.scan_options.exclude.push(pattern.into());
+        self
+    }
+
+    /// Directory names not descended into (replaces `DEFAULT_SKIPPED_DIRS`)
+    pub fn skipped_dirs<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.scan_options.skipped_dirs = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Ignore untracked files when checking for uncommitted changes
+    pub fn ignore_untracked(mut self, ignore_untracked: bool) -> Self {
+        self.check_options.ignore_untracked = ignore_untracked;
+        self
+    }
+
+    /// Replace all scan options
+    pub fn scan_options(mut self, scan_options: ScanOptions) -> Self {
+        self.scan_options = scan_options;
+        self
+    }
+
+    /// Replace all check options
+    pub fn check_options(mut self, check_options: CheckOptions) -> Self {
+        self.check_options = check_options;
+        self
+    }
+
+    fn base_path(&self) -> Result<PathBuf> {
+        self.root
+            .canonicalize()
+            .context(format!("Failed to resolve path: {}", self.root.display()))
+    }
+
+    /// Find repositories without checking them
+    pub fn find(&self) -> Result<Vec<PathBuf>> {
+        find_repositories(&self.base_path()?, &self.scan_options)
+    }
+
+    /// Find and check repositories (sorted by path)
+    pub fn scan(&self) -> Result<Vec<RepoResult>> {
+        scan_repositories(&self.base_path()?, &self.scan_options, &self.check_options)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::process::Command;
+    use crate::test_util::git;
     use tempfile::TempDir;
 
     #[test]
@@ -83,11 +290,7 @@ mod tests {
         assert!(!is_git_repository(dir.path()));
 
         // After git init, it becomes a Git repository
-        Command::new("git")
-            .args(["init"])
-            .current_dir(dir.path())
-            .output()
-            .unwrap();
+        git(dir.path(), &["init"]);
         assert!(is_git_repository(dir.path()));
     }
 
@@ -104,20 +307,122 @@ mod tests {
         fs::create_dir(&repo2).unwrap();
         fs::create_dir(&not_repo).unwrap();
 
-        Command::new("git")
-            .args(["init"])
-            .current_dir(&repo1)
-            .output()
-            .unwrap();
-        Command::new("git")
-            .args(["init"])
-            .current_dir(&repo2)
-            .output()
-            .unwrap();
-
-        let repos = find_repositories(base.path(), false);
+        git(&repo1, &["init"]);
+        git(&repo2, &["init"]);
+
+        let repos = find_repositories(base.path(), &ScanOptions::default()).unwrap();
         assert_eq!(repos.len(), 2);
         assert!(repos[0].ends_with("repo_a"));
         assert!(repos[1].ends_with("repo_b"));
     }
+
+    #[test]
+    fn test_is_git_repository_gitfile() {
+        let base = TempDir::new().unwrap();
+        let repo = base.path().join("repo");
+        let git_dir = base.path().join("repo.git");
+        fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "--separate-git-dir", git_dir.to_str().unwrap()]);
+        assert!(repo.join(".git").is_file());
+        assert!(is_git_repository(&repo));
+
+        // Dangling gitfile is not a repository
+        let dangling = base.path().join("dangling");
+        fs::create_dir(&dangling).unwrap();
+        fs::write(dangling.join(".git"), "gitdir: ../missing\n").unwrap();
+        assert!(!is_git_repository(&dangling));
+    }
+
+    #[test]
+    fn test_find_repositories_max_depth() {
+        let base = TempDir::new().unwrap();
+
+        // ~/src/<host>/<org>/<repo> layout
+        let deep = base.path().join("github.com/org/deep");
+        // Repositories inside a repository or a skipped directory are not reported
+        let nested = deep.join("nested");
+        let skipped = base.path().join("node_modules/pkg");
+        for path in [&deep, &nested, &skipped] {
+            fs::create_dir_all(path).unwrap();
+            git(path, &["init"]);
+        }
+
+        let options = ScanOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(find_repositories(base.path(), &options).unwrap().is_empty());
+
+        let options = ScanOptions {
+            max_depth: 3,
+            ..Default::default()
+        };
+        assert_eq!(find_repositories(base.path(), &options).unwrap(), vec![deep.clone()]);
+
+        // Without skipped directories, node_modules is searched as well
+        let options = ScanOptions {
+            max_depth: 3,
+            skipped_dirs: Vec::new(),
+            ..Default::default()
+        };
+        assert_eq!(find_repositories(base.path(), &options).unwrap(), vec![deep.clone(), skipped.clone()]);
+
+        let options = ScanOptions {
+            max_depth: usize::MAX,
+            ..Default::default()
+        };
+        assert_eq!(find_repositories(base.path(), &options).unwrap(), vec![deep]);
+    }
+
+    #[test]
+    fn test_find_repositories_include_exclude() {
+        let base = TempDir::new().unwrap();
+        let app = base.path().join("apps/app");
+        let tool = base.path().join("tools/tool");
+        let vendored = base.path().join("vendor/lib");
+        for path in [&app, &tool, &vendored] {
+            fs::create_dir_all(path).unwrap();
+            git(path, &["init"]);
+        }
+
+        let options = ScanOptions {
+            max_depth: 2,
+            exclude: vec!["vendor/*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            find_repositories(base.path(), &options).unwrap(),
+            vec![app.clone(), tool]
+        );
+
+        let options = ScanOptions {
+            max_depth: 2,
+            include: vec!["apps/*".to_string(), "vendor/**".to_string()],
+            exclude: vec!["vendor".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(find_repositories(base.path(), &options).unwrap(), vec![app]);
+
+        let options = ScanOptions {
+            exclude: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(find_repositories(base.path(), &options).is_err());
+    }
+
+    #[test]
+    fn test_scanner_builder() {
+        let base = TempDir::new().unwrap();
+        let nested = base.path().join("github.com/app");
+        fs::create_dir_all(&nested).unwrap();
+        git(&nested, &["init"]);
+
+        assert!(Scanner::new(base.path()).find().unwrap().is_empty());
+
+        let results = Scanner::new(base.path()).depth(2).scan().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, nested.canonicalize().unwrap());
+
+        assert!(Scanner::new(base.path().join("missing")).scan().is_err());
+    }
 }
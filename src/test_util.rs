@@ -0,0 +1,46 @@
+//! Repository setup shared by the unit tests
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// Run git in `dir` with a test identity and return its trimmed stdout
+pub(crate) fn git(dir: &Path, args: &[&str]) -> String {
+    let output = git_with_env(dir, args, &[]);
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Run git in `dir` with a test identity and extra environment variables
+pub(crate) fn git_with_env(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Output {
+    Command::new("git")
+        .args(["-c", "user.email=test@test.com", "-c", "user.name=Test"])
+        .args(args)
+        .envs(env.iter().copied())
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+/// An empty repository with a test identity in its config
+pub(crate) fn setup_git_repo() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    git(dir.path(), &["init"]);
+    git(dir.path(), &["config", "user.email", "test@test.com"]);
+    git(dir.path(), &["config", "user.name", "Test"]);
+    dir
+}
+
+/// A bare `remote.git` and a clone of it in `repo`, whose `main` branch has
+/// one commit pushed (and tracks `origin/main`). `env` applies to every git
+/// command. Returns the temporary directory, the remote and the clone.
+pub(crate) fn setup_cloned_repo(env: &[(&str, &str)]) -> (TempDir, PathBuf, PathBuf) {
+    let base = TempDir::new().unwrap();
+    let remote = base.path().join("remote.git");
+    let repo = base.path().join("repo");
+    git_with_env(base.path(), &["init", "--bare", "remote.git"], env);
+    git_with_env(base.path(), &["clone", remote.to_str().unwrap(), "repo"], env);
+    git_with_env(&repo, &["checkout", "-q", "-b", "main"], env);
+    git_with_env(&repo, &["commit", "--allow-empty", "-m", "initial"], env);
+    git_with_env(&repo, &["push", "-u", "origin", "main"], env);
+    (base, remote, repo)
+}
@@ -1,8 +1,12 @@
-use serde::Serialize;
+use crate::backend::Backend;
+use crate::checks::Checks;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Repository check status
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Status {
     /// Safe to delete
@@ -24,7 +28,7 @@ impl std::fmt::Display for Status {
 }
 
 /// Reason for the check result
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Reason {
     /// Uncommitted changes exist
@@ -33,10 +37,46 @@ pub enum Reason {
     StashExists,
     /// Local-only commits exist
     LocalOnlyCommits,
+    /// Branch tips not contained in any ref that currently exists on a remote
+    BranchesNotOnRemote(Vec<String>),
+    /// Tags pointing at commits not on any remote
+    LocalOnlyTags,
+    /// Commits only reachable from a detached HEAD
+    DetachedHeadCommits,
+    /// Commits only reachable from the reflog
+    ReflogOnlyCommits,
+    /// A linked worktree has local changes
+    WorktreeUnsafe,
+    /// A linked worktree could not be checked
+    WorktreeUnknown,
+    /// A submodule has local-only changes
+    SubmoduleUnsafe,
+    /// A submodule could not be determined safe
+    SubmoduleUnknown,
     /// No remote tracking refs
     NoRemoteRefs,
+    /// Remote tracking refs were not updated within the maximum fetch age
+    StaleRemoteRefs,
+    /// Every remote points to a missing or unreachable location
+    RemoteUnreachable(Vec<String>),
+    /// Rebase in progress
+    RebaseInProgress,
+    /// Merge in progress
+    MergeInProgress,
+    /// Cherry-pick in progress
+    CherryPickInProgress,
+    /// Revert in progress
+    RevertInProgress,
+    /// Bisect in progress
+    BisectInProgress,
     /// Git error occurred
     GitError(String),
+    /// `git fetch` failed, so remote-tracking refs may be stale
+    FetchFailed(String),
+    /// A command was killed after exceeding the timeout
+    Timeout(String),
+    /// Reported by a custom check
+    Custom { check: String, message: String },
     /// All checks passed
     AllChecksOk,
 }
@@ -47,17 +87,173 @@ impl std::fmt::Display for Reason {
             Reason::UncommittedChanges => write!(f, "Uncommitted changes exist"),
             Reason::StashExists => write!(f, "Stash entries exist"),
             Reason::LocalOnlyCommits => write!(f, "Local-only commits exist"),
+            Reason::BranchesNotOnRemote(branches) => {
+                write!(f, "Branches not on any remote: {}", branches.join(", "))
+            }
+            Reason::LocalOnlyTags => write!(f, "Local-only tags exist"),
+            Reason::DetachedHeadCommits => write!(f, "Commits only reachable from detached HEAD"),
+            Reason::ReflogOnlyCommits => write!(f, "Commits only reachable from the reflog"),
+            Reason::WorktreeUnsafe => write!(f, "Linked worktree has local changes"),
+            Reason::WorktreeUnknown => write!(f, "Linked worktree could not be checked"),
+            Reason::SubmoduleUnsafe => write!(f, "Submodule has local-only changes"),
+            Reason::SubmoduleUnknown => write!(f, "Submodule status is unknown"),
             Reason::NoRemoteRefs => write!(f, "No remote tracking refs found"),
+            Reason::StaleRemoteRefs => write!(f, "Remote tracking refs are stale"),
+            Reason::RemoteUnreachable(remotes) => write!(f, "Remotes unreachable: {}", remotes.join(", ")),
+            Reason::RebaseInProgress => write!(f, "Rebase in progress"),
+            Reason::MergeInProgress => write!(f, "Merge in progress"),
+            Reason::CherryPickInProgress => write!(f, "Cherry-pick in progress"),
+            Reason::RevertInProgress => write!(f, "Revert in progress"),
+            Reason::BisectInProgress => write!(f, "Bisect in progress"),
             Reason::GitError(msg) => write!(f, "Git error: {}", msg),
+            Reason::FetchFailed(msg) => write!(f, "Fetch failed: {}", msg),
+            Reason::Timeout(command) => write!(f, "Timed out: {}", command),
+            Reason::Custom { check, message } => write!(f, "{}: {}", check, message),
             Reason::AllChecksOk => write!(f, "All checks passed"),
         }
     }
 }
 
+/// Options controlling which checks are run
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Ignore untracked files in Check A
+    pub ignore_untracked: bool,
+    /// Checks to run (built-in and custom)
+    pub checks: Checks,
+    /// How the built-in checks read the repository
+    pub backend: Backend,
+    /// Kill commands (git and `[[check]]` commands) running longer than this
+    pub timeout: Option<Duration>,
+    /// Maximum number of concurrent fetches of the `fetch` check (0: no limit)
+    pub fetch_jobs: usize,
+    /// Repositories whose remote tracking refs are older than this are UNKNOWN
+    pub max_fetch_age: Option<Duration>,
+    /// Contact network remotes (`git ls-remote`) in the remote health and tag checks
+    pub probe_remotes: bool,
+    /// Repositories protected from deletion by the config file
+    pub protected_paths: Vec<PathBuf>,
+}
+
+/// Options controlling which directories are scanned
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Include the base directory itself as a target
+    pub include_dot: bool,
+    /// Maximum directory depth to search (1 = immediate subdirectories only)
+    pub max_depth: usize,
+    /// Glob patterns (relative to the base path) repositories must match
+    pub include: Vec<String>,
+    /// Glob patterns (relative to the base path) excluded from the scan
+    pub exclude: Vec<String>,
+    /// Directory names not descended into (defaults to `scanner::DEFAULT_SKIPPED_DIRS`)
+    pub skipped_dirs: Vec<String>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            include_dot: false,
+            max_depth: 1,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            skipped_dirs: crate::scanner::DEFAULT_SKIPPED_DIRS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Why a repository is protected from deletion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protection {
+    /// `repo-check-keep` marker file in the git directory
+    MarkerFile,
+    /// `repo-check.protect=true` in git config
+    GitConfig,
+    /// Listed in `protected` in the config file
+    ConfigList,
+}
+
+impl std::fmt::Display for Protection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protection::MarkerFile => write!(f, "marker file"),
+            Protection::GitConfig => write!(f, "git config"),
+            Protection::ConfigList => write!(f, "config file"),
+        }
+    }
+}
+
+/// HEAD and refs at check time, used to detect changes before deletion
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoFingerprint {
+    /// HEAD commit (None for an unborn HEAD)
+    pub head: Option<String>,
+    /// Every ref (including refs/stash) and the object it points to
+    pub refs: BTreeMap<String, String>,
+}
+
+impl RepoFingerprint {
+    /// Describe differences to a newer fingerprint
+    fn diff(&self, other: &RepoFingerprint) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.head != other.head {
+            changes.push(format!(
+                "HEAD: {} -> {}",
+                self.head.as_deref().unwrap_or("(none)"),
+                other.head.as_deref().unwrap_or("(none)")
+            ));
+        }
+        for (name, sha) in &self.refs {
+            match other.refs.get(name) {
+                None => changes.push(format!("Ref removed: {}", name)),
+                Some(new_sha) if new_sha != sha => {
+                    changes.push(format!("Ref moved: {} {} -> {}", name, sha, new_sha))
+                }
+                Some(_) => {}
+            }
+        }
+        for name in other.refs.keys().filter(|name| !self.refs.contains_key(*name)) {
+            changes.push(format!("Ref added: {}", name));
+        }
+        changes
+    }
+}
+
+/// A local branch, its upstream and its tip commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchInfo {
+    /// Branch name (without `refs/heads/`)
+    pub name: String,
+    /// Upstream branch (e.g. `origin/main`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// The upstream is configured but no longer exists
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub upstream_gone: bool,
+    /// Commits not on the upstream
+    pub ahead: usize,
+    /// Upstream commits not on the branch
+    pub behind: usize,
+    /// Commits not reachable from any remote tracking ref
+    /// (only counted in repositories with local-only commits)
+    pub local_only_commit_count: usize,
+    /// Tip commit
+    pub tip: String,
+    /// Subject of the tip commit
+    pub subject: String,
+    /// Committer date of the tip commit (seconds since the Unix epoch)
+    pub date: u64,
+}
+
 /// Repository check result
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepoResult {
-    /// Repository path
+    /// Repository path (`path_bytes` in JSON if not valid UTF-8)
+    #[serde(flatten, with = "crate::paths::path_field")]
     pub path: PathBuf,
     /// Check status
     pub status: Status,
@@ -69,9 +265,33 @@ pub struct RepoResult {
     pub stash_count: usize,
     /// Number of local-only commits
     pub local_only_commit_count: usize,
+    /// Number of local-only tags
+    pub local_only_tag_count: usize,
+    /// Number of commits only reachable from a detached HEAD
+    pub detached_head_commit_count: usize,
+    /// Number of commits only reachable from the reflog
+    pub reflog_only_commit_count: usize,
+    /// Seconds since the remote tracking refs were last fetched or updated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_fetch_age: Option<u64>,
+    /// Local branches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<BranchInfo>,
+    /// Protected from deletion (and why)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protected: Option<Protection>,
+    /// Linked worktrees owned by this repository
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worktrees: Vec<RepoResult>,
+    /// Initialized submodules (checked recursively)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub submodules: Vec<RepoResult>,
     /// Error messages (if any)
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub errors: Vec<String>,
+    /// HEAD and refs at check time (not serialized)
+    #[serde(skip)]
+    pub fingerprint: RepoFingerprint,
 }
 
 impl RepoResult {
@@ -83,7 +303,16 @@ impl RepoResult {
             dirty_count: 0,
             stash_count: 0,
             local_only_commit_count: 0,
+            local_only_tag_count: 0,
+            detached_head_commit_count: 0,
+            reflog_only_commit_count: 0,
+            last_fetch_age: None,
+            branches: Vec::new(),
+            protected: None,
+            worktrees: Vec::new(),
+            submodules: Vec::new(),
             errors: Vec::new(),
+            fingerprint: RepoFingerprint::default(),
         }
     }
 
@@ -101,6 +330,68 @@ impl RepoResult {
         self.reasons.push(reason);
     }
 
+    /// Describe what differs in a newer result for the same repository
+    /// (status, reasons, protection, counts, worktrees, submodules, HEAD and refs)
+    pub fn diff(&self, other: &RepoResult) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.status != other.status {
+            changes.push(format!("Status: {} -> {}", self.status, other.status));
+        }
+        if self.protected != other.protected {
+            let describe = |p: Option<Protection>| p.map_or("(none)".to_string(), |p| p.to_string());
+            changes.push(format!(
+                "Protection: {} -> {}",
+                describe(self.protected),
+                describe(other.protected)
+            ));
+        }
+        for reason in other.reasons.iter().filter(|r| !self.reasons.contains(r)) {
+            changes.push(format!("New reason: {}", reason));
+        }
+        for reason in self.reasons.iter().filter(|r| !other.reasons.contains(r)) {
+            changes.push(format!("Reason gone: {}", reason));
+        }
+
+        let counts = [
+            ("Dirty files", self.dirty_count, other.dirty_count),
+            ("Stash entries", self.stash_count, other.stash_count),
+            ("Local-only commits", self.local_only_commit_count, other.local_only_commit_count),
+            ("Local-only tags", self.local_only_tag_count, other.local_only_tag_count),
+            ("Detached HEAD commits", self.detached_head_commit_count, other.detached_head_commit_count),
+            ("Reflog-only commits", self.reflog_only_commit_count, other.reflog_only_commit_count),
+        ];
+        for (label, old, new) in counts {
+            if old != new {
+                changes.push(format!("{}: {} -> {}", label, old, new));
+            }
+        }
+
+        // Worktrees and submodules, matched by path
+        let children = [
+            ("Worktree", &self.worktrees, &other.worktrees),
+            ("Submodule", &self.submodules, &other.submodules),
+        ];
+        for (label, old, new) in children {
+            for child in old {
+                match new.iter().find(|c| c.path == child.path) {
+                    Some(new_child) => changes.extend(
+                        child
+                            .diff(new_child)
+                            .into_iter()
+                            .map(|c| format!("{} {}: {}", label, child.path.display(), c)),
+                    ),
+                    None => changes.push(format!("{} removed: {}", label, child.path.display())),
+                }
+            }
+            for child in new.iter().filter(|c| !old.iter().any(|o| o.path == c.path)) {
+                changes.push(format!("{} added: {}", label, child.path.display()));
+            }
+        }
+
+        changes.extend(self.fingerprint.diff(&other.fingerprint));
+        changes
+    }
+
     /// Finalize as SAFE
     pub fn finalize_safe(&mut self) {
         if self.status == Status::Safe {
@@ -109,6 +400,41 @@ impl RepoResult {
     }
 }
 
+/// How repositories are removed in delete mode
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DeleteMethod {
+    /// Permanent deletion (rm -rf)
+    #[default]
+    Remove,
+    /// Move to the desktop trash (falls back to rm -rf on confirmation)
+    Trash,
+    /// Move into the given quarantine directory
+    Quarantine(PathBuf),
+}
+
+/// A repository to delete, with the options of the target directory it was found in
+#[derive(Debug, Clone, Copy)]
+pub struct DeleteCandidate<'a> {
+    pub result: &'a RepoResult,
+    /// Options used for the scan (re-used for the recheck before deletion)
+    pub check_options: &'a CheckOptions,
+    /// How the repository is removed
+    pub method: &'a DeleteMethod,
+}
+
+/// Options controlling delete mode
+#[derive(Debug, Clone, Default)]
+pub struct DeleteOptions {
+    /// Skip confirmation prompts
+    pub skip_confirm: bool,
+    /// Write a verified git bundle here before deleting
+    pub bundle_dir: Option<PathBuf>,
+    /// Append a journal entry for every deletion or skip
+    pub journal_path: Option<PathBuf>,
+    /// Write a re-clone manifest of deleted repositories here
+    pub manifest_path: Option<PathBuf>,
+}
+
 /// User response for deletion confirmation
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DeleteConfirm {
@@ -149,4 +475,34 @@ mod tests {
         // Should NOT add AllChecksOk when UNSAFE
         assert!(!result.reasons.contains(&Reason::AllChecksOk));
     }
+
+    #[test]
+    fn test_diff() {
+        let mut old = RepoResult::new(PathBuf::from("/test"));
+        old.fingerprint.refs.insert("refs/heads/main".into(), "aaa".into());
+        old.fingerprint.refs.insert("refs/heads/gone".into(), "bbb".into());
+        let mut new = old.clone();
+        assert!(old.diff(&new).is_empty());
+
+        new.mark_unsafe(Reason::StashExists);
+        new.stash_count = 1;
+        new.fingerprint.refs.insert("refs/heads/main".into(), "ccc".into());
+        new.fingerprint.refs.remove("refs/heads/gone");
+        new.fingerprint.refs.insert("refs/stash".into(), "ddd".into());
+        new.protected = Some(Protection::MarkerFile);
+
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes,
+            vec![
+                "Status: SAFE -> UNSAFE",
+                "Protection: (none) -> marker file",
+                "New reason: Stash entries exist",
+                "Stash entries: 0 -> 1",
+                "Ref removed: refs/heads/gone",
+                "Ref moved: refs/heads/main aaa -> ccc",
+                "Ref added: refs/stash",
+            ]
+        );
+    }
 }